@@ -0,0 +1,7 @@
+-- the table the layers are stored in, databases from before migrations already have it
+create table if not exists tl_layer
+(
+    layer_id     int       not null primary key,
+    layer        text      not null,
+    release_date timestamp not null
+);
@@ -0,0 +1,11 @@
+-- layers of different schema families (api, mtproto, secret, custom) have independent numbers
+do $$
+begin
+    create type schema_family as enum ('api', 'mtproto', 'secret', 'custom');
+exception
+    when duplicate_object then null;
+end $$;
+
+alter table tl_layer add column if not exists family schema_family not null default 'api';
+alter table tl_layer drop constraint if exists tl_layer_pkey;
+alter table tl_layer add primary key (family, layer_id);
@@ -0,0 +1,10 @@
+-- release notes of a layer compared with the layer stored before it, written by the github job
+create table if not exists layer_changelog
+(
+    family            schema_family not null,
+    layer_id          int           not null,
+    previous_layer_id int           not null,
+    markdown          text          not null,
+    html              text          not null,
+    primary key (family, layer_id)
+);
@@ -0,0 +1,6 @@
+-- blob sha of every file of the schemes repository the github job already read, files are only fetched again when it changes
+create table if not exists schema_file
+(
+    path text not null primary key,
+    sha  text not null
+);
@@ -0,0 +1,236 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use chrono::NaiveDateTime;
+use eyre::{bail, eyre};
+use crate::models::requests::{SchemaFamily, SchemaSection};
+use crate::models::tl_layer::TlLayer;
+use crate::prelude::Res;
+use crate::tl::{self, codec::{self, TlCodec}, constructor_id, inspector, lint::LintConfig, TlSchema};
+use crate::tl::tl_flag::TlFlagField;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_type_ref::TlTypeRef;
+
+const USAGE: &str = "usage:
+    schema-tools                                                      start the server
+    schema-tools lint <file.tl> [--section api|mtproto|all] [--checks a,b]   lint a schema file
+    schema-tools bench <file.tl>...                                   parse each file as a layer and report the time and heap it takes
+    schema-tools inspect <payload> <file.tl>...                       decode a hex or base64 payload with each schema";
+
+/// bytes the process has allocated and not freed, and the most it had since `bench` reset it
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// the system allocator, counting bytes for `bench`. the server pays two relaxed atomics per allocation
+struct CountingAllocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocated(size: usize) {
+    let now = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(now, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+        new
+    }
+}
+
+/// runs a command line tool, `args` doesn't include the binary name
+pub fn run(args: &[String]) -> Res {
+    match args.first().map(|a| a.as_str()) {
+        Some("lint") => lint(&args[1..]),
+        Some("bench") => bench(&args[1..]),
+        Some("inspect") => inspect(&args[1..]),
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(command) => bail!("unknown command `{command}`\n{USAGE}"),
+        None => bail!("{USAGE}"),
+    }
+}
+
+fn lint(args: &[String]) -> Res {
+    let path = args.first().ok_or_else(|| eyre!("missing schema file\n{USAGE}"))?;
+    let section = match option(args, "--section") {
+        Some(s) => serde_json::from_value(serde_json::Value::String(s.to_owned()))?,
+        None => SchemaSection::All,
+    };
+    let config = match option(args, "--checks") {
+        Some(c) => LintConfig::from_names(c).map_err(|e| eyre!(e))?,
+        None => LintConfig::default(),
+    };
+
+    let schema = read_schema(path)?;
+    for diagnostic in &schema.diagnostics {
+        eprintln!("{diagnostic}");
+    }
+    let report = tl::lint::lint(&schema, section, &config);
+    for finding in report.findings.values().flatten() {
+        println!("{path}:{finding}");
+    }
+    match report.count() {
+        0 => Ok(()),
+        n => bail!("found {n} problems"),
+    }
+}
+
+/// parses each file as its own layer, like a server starting with them, and measures the time and the heap it takes
+fn bench(args: &[String]) -> Res {
+    if args.is_empty() {
+        bail!("missing schema files\n{USAGE}");
+    }
+    let (base, started_at) = (ALLOCATED.load(Ordering::Relaxed), Instant::now());
+    PEAK.store(base, Ordering::Relaxed);
+    let layers = args.iter()
+        .enumerate()
+        .map(|(n, path)| {
+            let layer = std::fs::read_to_string(path)?;
+            let layer_id = tl::layer_metadata::read(&layer).layer.unwrap_or(n as i32);
+            Ok(TlLayer { family: SchemaFamily::Custom, layer_id, layer, release_date: NaiveDateTime::default() })
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let text_bytes = layers.iter().map(|l| l.layer.len()).sum::<usize>();
+    let count = layers.len();
+    let read = started_at.elapsed();
+
+    let started = Instant::now();
+    let schemas = layers.into_iter()
+        .map(tl::parse_schema)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| eyre!("a file could not be parsed, run `lint` on it for details"))?;
+    let elapsed = started.elapsed();
+    let (held, peak) = (ALLOCATED.load(Ordering::Relaxed).saturating_sub(base), PEAK.load(Ordering::Relaxed) - base);
+
+    let references = schemas.iter().map(|s| identifiers(s).len()).sum::<usize>();
+    let (unique, interned_bytes) = tl::tl_ident::interned();
+    let definitions = schemas.iter()
+        .map(|s| s.objects(SchemaSection::All).map(|t| t.constructors.len()).sum::<usize>() + s.functions(SchemaSection::All).map(|(_, f)| f.len()).sum::<usize>())
+        .sum::<usize>();
+    let (constructors, functions) = tl::tl_shared::stored();
+
+    println!("read {count} layers ({text_bytes} bytes of text) in {read:?}");
+    println!("parsed them in {elapsed:?}, {:?} per layer", elapsed / count as u32);
+    println!("heap: {held} bytes held by the parsed layers, {peak} bytes at the peak, both with the text");
+    println!("definitions: {definitions} in all layers, {} stored ({constructors} constructors, {functions} functions)", constructors + functions);
+    println!("identifiers: {references} references to {unique} interned identifiers ({interned_bytes} bytes of text)");
+    Ok(())
+}
+
+/// decodes the payload with every file, reading as much as it can when it doesn't fit
+fn inspect(args: &[String]) -> Res {
+    let (payload, paths) = args.split_first().ok_or_else(|| eyre!("missing payload\n{USAGE}"))?;
+    if paths.is_empty() {
+        bail!("missing schema files\n{USAGE}");
+    }
+    let bytes = codec::parse_payload(payload)?;
+    println!("#{}, {} bytes", constructor_id::format_id(inspector::leading_id(&bytes)?), bytes.len());
+    for path in paths {
+        let schema = read_schema(path)?;
+        let inspection = TlCodec::new(&schema).inspect(&bytes);
+        match &inspection.error {
+            Some(e) => println!("{path}: {e}"),
+            None if inspection.trailing > 0 => println!("{path}: {} bytes left after the value", inspection.trailing),
+            None => println!("{path}: clean"),
+        }
+        if let Some(value) = &inspection.value {
+            println!("{}", serde_json::to_string_pretty(value)?);
+        }
+    }
+    Ok(())
+}
+
+/// every identifier a schema holds, the same name is returned once per place it appears in
+fn identifiers(schema: &TlSchema) -> Vec<&TlIdent> {
+    fn type_ref<'a>(t: &'a TlTypeRef, out: &mut Vec<&'a TlIdent>) {
+        match t {
+            TlTypeRef::Bare { name } | TlTypeRef::Boxed { name } | TlTypeRef::Generic { name } => out.push(name),
+            TlTypeRef::Vector { inner, .. } => type_ref(inner, out),
+            TlTypeRef::Flag { field, inner, .. } => {
+                out.push(field);
+                type_ref(inner, out);
+            }
+            TlTypeRef::Nat => {}
+        }
+    }
+    fn parameters<'a>(params: &'a [TlParameter], flags: &'a [TlFlagField], out: &mut Vec<&'a TlIdent>) {
+        for p in params {
+            out.push(&p.name);
+            type_ref(&p._type, out);
+        }
+        for f in flags {
+            out.push(&f.name);
+            out.extend(f.bits.iter().flat_map(|b| &b.parameters));
+        }
+    }
+
+    let mut out = vec![];
+    for tl_type in schema.objects(SchemaSection::All) {
+        out.push(&tl_type.name);
+        for p in &tl_type.common_parameters {
+            out.push(&p.name);
+            type_ref(&p._type, &mut out);
+        }
+        for c in &tl_type.constructors {
+            out.extend([&c.id, &c.computed_id, &c.name]);
+            out.extend(&c.namespace);
+            out.extend(&c.type_params);
+            parameters(&c.parameters, &c.flag_fields, &mut out);
+        }
+    }
+    for (ns, functions) in schema.functions(SchemaSection::All) {
+        out.push(ns);
+        for f in functions {
+            out.extend([&f.id, &f.computed_id, &f.name]);
+            out.extend(&f.type_params);
+            parameters(&f.parameters, &f.flag_fields, &mut out);
+            type_ref(&f.return_type, &mut out);
+        }
+    }
+    out
+}
+
+/// value after `name`, `--section api`
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).map(|a| a.as_str())
+}
+
+fn read_schema(path: &str) -> eyre::Result<tl::TlSchema> {
+    let content = std::fs::read_to_string(path)?;
+    let layer_id = tl::layer_metadata::read(&content).layer.unwrap_or_default();
+    let layer = TlLayer { family: SchemaFamily::Custom, layer_id, layer: content, release_date: NaiveDateTime::default() };
+    tl::parse_schema(layer).map_err(|diagnostics| {
+        let diagnostics = diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>().join("\n");
+        eyre!("could not parse {path}\n{diagnostics}")
+    })
+}
@@ -23,7 +23,7 @@ async fn get_namespace(State(state): State<AppState>, req: Validated<Json<GetNam
         .unwrap_or_else(|| ApiResponse::not_found("could not find the layer id or namespace"))
 }
 async fn history(State(state): State<AppState>, req: Validated<Json<HistoryRequest>>) -> impl IntoResponse {
-    let h = state.schema_manager.history(&req.name, DefinitionType::Function);
+    let h = state.schema_manager.history(&req, DefinitionType::Function);
     ApiResponse::ok("", Some(json!(h)))
 }
 
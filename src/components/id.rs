@@ -0,0 +1,27 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Router;
+use axum::routing::get;
+use serde_json::json;
+use crate::app_state::AppState;
+use crate::components::ApiResponse;
+use crate::models::requests::IdQuery;
+use crate::tl::constructor_id;
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/:id", get(lookup_id))
+        .with_state(state)
+}
+
+async fn lookup_id(Path(id): Path<String>, State(state): State<AppState>, Query(q): Query<IdQuery>) -> impl IntoResponse {
+    let ids = constructor_id::lookup_ids(&id);
+    if ids.is_empty() {
+        return ApiResponse::bad_request(format!("`{id}` is not a constructor id, use hex like `0x38fe25b7` or a signed or unsigned int"));
+    }
+    let hits = state.schema_manager.lookup_ids(&ids, q.family);
+    if hits.is_empty() {
+        return ApiResponse::not_found(format!("no layer defines `{id}`"));
+    }
+    ApiResponse::ok(format!("found {} definitions", hits.len()), Some(json!({"results":hits})))
+}
@@ -1,7 +1,7 @@
-use axum::{extract::{Path, Json, State}, response::IntoResponse, Router, routing::{get, post}};
+use axum::{extract::{Path, Json, Query, State}, http::header, response::{Html, IntoResponse, Response}, Router, routing::{get, post}};
 use axum_valid::{Validated};
 use serde_json::json;
-use crate::{app_state::AppState, components::{ApiResponse, root}, db, models::{requests::SearchLayerRequest}};
+use crate::{app_state::AppState, components::{ApiResponse, root}, db, models::{requests::{ChangelogQuery, DecodeRequest, EncodeRequest, FamilyQuery, GraphExportQuery, GraphQuery, InspectRequest, LayerDiffQuery, LintQuery, SearchLayerRequest, LayerQuery}}, tl::{changelog::TlChangelogFormat, codec, lint::LintConfig, tl_graph::{TlDirection, TlGraphFilter}}};
 
 pub fn routes(state: AppState) -> Router {
     Router::new()
@@ -12,28 +12,39 @@ pub fn routes(state: AppState) -> Router {
         .route("/types", get(get_types))
         .route("/:id", get(get_layer))
         .route("/:id/compact", get(get_compact_layer))
+        .route("/:id/tl", get(get_layer_text))
         .route("/:id/namespace", get(get_namespace_in_layer))
         .route("/:id/type", get(types_in_layer))
+        .route("/:id/flags/:name", get(get_flag_layout))
+        .route("/:id/lint", get(lint_layer))
+        .route("/:id/dependencies/:name", get(dependencies))
+        .route("/:id/dependents/:name", get(dependents))
+        .route("/:id/graph", get(export_graph))
+        .route("/:id/changelog", get(changelog))
+        .route("/:id/encode", post(encode))
+        .route("/:id/decode", post(decode))
+        .route("/diff", get(diff_layers))
+        .route("/inspect", post(inspect_payload))
         .route("/search", post(search_in_layer))
         .route("/search/filters", get(get_search_filters))
         .route("/search/ready", get(engine_ready))
         .with_state(state)
 }
 
-async fn get_types(State(state): State<AppState>) -> impl IntoResponse {
-    let ns = state.schema_manager.get_type_names(None);
+async fn get_types(State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    let ns = state.schema_manager.get_type_names(q.family, None, q.section);
     ApiResponse::ok("", Some(json!(ns)))
 }
-async fn types_in_layer(Path(layer_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
-    let ns = state.schema_manager.get_type_names(Some(layer_id));
+async fn types_in_layer(Path(layer_id): Path<i32>, State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    let ns = state.schema_manager.get_type_names(q.family, Some(layer_id), q.section);
     ApiResponse::ok("", Some(json!(ns)))
 }
-async fn get_namespaces(State(state): State<AppState>) -> impl IntoResponse {
-    let ns = state.schema_manager.get_namespace(None);
+async fn get_namespaces(State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    let ns = state.schema_manager.get_namespace(q.family, None, q.section);
     ApiResponse::ok("", Some(json!(ns)))
 }
-async fn get_namespace_in_layer(Path(layer_id): Path<u32>, State(state): State<AppState>) -> impl IntoResponse {
-    let ns = state.schema_manager.get_namespace(Some(layer_id as _));
+async fn get_namespace_in_layer(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    let ns = state.schema_manager.get_namespace(q.family, Some(layer_id as _), q.section);
     ApiResponse::ok("", Some(json!(ns)))
 }
 async fn get_search_filters(State(state): State<AppState>) -> impl IntoResponse {
@@ -51,26 +62,119 @@ async fn search_in_layer(State(state): State<AppState>, req: Validated<Json<Sear
         .map(|res| ApiResponse::ok(res.to_string(), Some(json!({"search_results":res.results}))))
         .map_err(|e| ApiResponse::internal(e.to_string()))
 }
-async fn layer_release_dates(State(state): State<AppState>) -> impl IntoResponse {
-    let mut dates = state.schema_manager.release_dates();
+async fn layer_release_dates(State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    let mut dates = state.schema_manager.release_dates(q.family);
     dates.sort_by(|d, d2| d2.release_date.cmp(&d.release_date));
     ApiResponse::ok("", Some(json!({"release_dates":dates})))
 }
-async fn get_compact_layer(Path(layer_id): Path<u32>, State(state): State<AppState>) -> impl IntoResponse {
-    let layer = state.schema_manager.get_compact_layer(layer_id as _);
+async fn get_compact_layer(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    let layer = state.schema_manager.get_compact_layer(q.family, layer_id as _, q.section);
     if layer.is_empty() {
-        ApiResponse::not_found(format!("layer {layer_id} doesn't exist or it's not loaded yet"))
+        ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist or it's not loaded yet", q.family))
     } else {
         ApiResponse::ok("", Some(json!({"compact_layer": layer})))
     }
 }
-async fn get_layer(Path(layer_id): Path<u32>, State(state): State<AppState>) -> impl IntoResponse {
-    state.schema_manager.get_layer(layer_id as _)
+async fn get_layer_text(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<LayerQuery>) -> Response {
+    state.schema_manager.get_layer_text(q.family, layer_id as _, q.section)
+        .map(|text| text.into_response())
+        .unwrap_or_else(|| ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist or it's not loaded yet", q.family)).into_response())
+}
+async fn get_layer(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    state.schema_manager.get_layer(q.family, layer_id as _, q.section)
         .map(|l| ApiResponse::ok("", Some(json!({"layer":l}))))
-        .unwrap_or(ApiResponse::not_found(format!("layer {layer_id} doesn't exist or it's not loaded yet")))
+        .unwrap_or(ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist or it's not loaded yet", q.family)))
+}
+async fn get_flag_layout(Path((layer_id, name)): Path<(u32, String)>, State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    state.schema_manager.get_flag_layout(q.family, layer_id as _, &name, q.section)
+        .map(|l| ApiResponse::ok(format!("found {} flag fields", l.fields.len()), Some(json!({"flags":l}))))
+        .unwrap_or_else(|| ApiResponse::not_found(format!("could not find {name} in {} layer {layer_id}", q.family)))
+}
+async fn lint_layer(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<LintQuery>) -> impl IntoResponse {
+    let config = match q.checks.as_deref().map(LintConfig::from_names).transpose() {
+        Ok(config) => config.unwrap_or_default(),
+        Err(e) => return ApiResponse::bad_request(e),
+    };
+    state.schema_manager.lint(q.family, layer_id as _, q.section, &config)
+        .map(|r| ApiResponse::ok(format!("found {} problems", r.count()), Some(json!({"lint":r}))))
+        .unwrap_or_else(|| ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist or it's not loaded yet", q.family)))
+}
+async fn dependencies(Path((layer_id, name)): Path<(u32, String)>, State(state): State<AppState>, Query(q): Query<GraphQuery>) -> impl IntoResponse {
+    closure(&state, layer_id, &name, &q, TlDirection::Dependencies)
+}
+async fn dependents(Path((layer_id, name)): Path<(u32, String)>, State(state): State<AppState>, Query(q): Query<GraphQuery>) -> impl IntoResponse {
+    closure(&state, layer_id, &name, &q, TlDirection::Dependents)
+}
+fn closure(state: &AppState, layer_id: u32, name: &str, q: &GraphQuery, direction: TlDirection) -> ApiResponse {
+    let Some(graph) = state.schema_manager.graph(q.family, layer_id as _, q.section) else {
+        return ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist or it's not loaded yet", q.family));
+    };
+    let Some(root) = graph.find(name, q.kind) else {
+        return ApiResponse::not_found(format!("could not find {name} in {} layer {layer_id}", q.family));
+    };
+    let filter = TlGraphFilter { namespace: q.namespace.clone(), collapse_primitives: q.collapse_primitives };
+    let graph = graph.closure(root, direction, q.depth, &filter);
+    ApiResponse::ok(format!("{name} has {} {direction}", graph.nodes.len() - 1), Some(json!({"graph":graph})))
+}
+async fn export_graph(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<GraphExportQuery>) -> Response {
+    if q.root.is_empty() {
+        return ApiResponse::bad_request("`root` is missing, pass the name of a type, constructor or function").into_response();
+    }
+    let Some(graph) = state.schema_manager.graph(q.family, layer_id as _, q.section) else {
+        return ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist or it's not loaded yet", q.family)).into_response();
+    };
+    let Some(root) = graph.find(&q.root, q.kind) else {
+        return ApiResponse::not_found(format!("could not find {} in {} layer {layer_id}", q.root, q.family)).into_response();
+    };
+    let filter = TlGraphFilter { namespace: q.namespace, collapse_primitives: q.collapse_primitives };
+    graph.closure(root, q.direction, q.depth, &filter).render(q.format).into_response()
+}
+async fn encode(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<FamilyQuery>, Json(req): Json<EncodeRequest>) -> impl IntoResponse {
+    match state.schema_manager.encode(q.family, layer_id as _, &req) {
+        Some(Ok(bytes)) => ApiResponse::ok(format!("encoded {} bytes", bytes.len()), Some(json!({"payload":codec::to_hex(&bytes)}))),
+        Some(Err(e)) => ApiResponse::bad_request(e.to_string()),
+        None => ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist or it's not loaded yet", q.family)),
+    }
+}
+async fn decode(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<FamilyQuery>, Json(req): Json<DecodeRequest>) -> impl IntoResponse {
+    match state.schema_manager.decode(q.family, layer_id as _, &req) {
+        Some(Ok(value)) => ApiResponse::ok("", Some(json!({"value":value}))),
+        Some(Err(e)) => ApiResponse::bad_request(e.to_string()),
+        None => ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist or it's not loaded yet", q.family)),
+    }
+}
+/// the changelog stored by the github job, or one made from the loaded layers for the ones it didn't see
+async fn changelog(Path(layer_id): Path<u32>, State(state): State<AppState>, Query(q): Query<ChangelogQuery>) -> Response {
+    let text = match db::layer_changelog::get(&state.db, q.family, layer_id as _).await {
+        Ok(Some(stored)) => stored.text(q.format),
+        Ok(None) => match state.schema_manager.changelog(q.family, layer_id as _) {
+            Some(c) => c.render(q.format),
+            None => return ApiResponse::not_found(format!("{} layer {layer_id} doesn't exist, it's not loaded yet or it's the first layer", q.family)).into_response(),
+        },
+        Err(e) => return ApiResponse::internal(e.to_string()).into_response(),
+    };
+    match q.format {
+        TlChangelogFormat::Md => ([(header::CONTENT_TYPE, "text/markdown; charset=utf-8")], text).into_response(),
+        TlChangelogFormat::Html => Html(text).into_response(),
+    }
+}
+async fn diff_layers(State(state): State<AppState>, Query(q): Query<LayerDiffQuery>) -> impl IntoResponse {
+    state.schema_manager.diff(q.family, q.from as _, q.to as _, q.section, q.breaking_only)
+        .map(|d| ApiResponse::ok(format!("{} differences between layer {} and {}", d.count(), q.from, q.to), Some(json!({"diff":d}))))
+        .unwrap_or_else(|| ApiResponse::not_found(format!("{} layer {} or {} doesn't exist or it's not loaded yet", q.family, q.from, q.to)))
+}
+async fn inspect_payload(State(state): State<AppState>, Json(req): Json<InspectRequest>) -> impl IntoResponse {
+    match state.schema_manager.inspect(&req) {
+        Ok(r) if r.results.is_empty() => ApiResponse::not_found(format!("no layer defines #{}", r.id)),
+        Ok(r) => {
+            let clean = r.results.iter().filter(|d| d.clean).map(|d| d.layers.len()).sum::<usize>();
+            ApiResponse::ok(format!("#{} decodes cleanly under {clean} layers", r.id), Some(json!({"inspection":r})))
+        }
+        Err(e) => ApiResponse::bad_request(e.to_string()),
+    }
 }
-async fn layer_ids(State(state): State<AppState>) -> impl IntoResponse {
-    db::tl_layer::get_ids(&state.db).await
+async fn layer_ids(State(state): State<AppState>, Query(q): Query<LayerQuery>) -> impl IntoResponse {
+    db::tl_layer::get_ids(&state.db, q.family).await
         .map(|mut ids| {
             ids.sort();
             ApiResponse::ok(format!("there are a total {} layers", ids.len()), Some(json!({"layers":ids})))
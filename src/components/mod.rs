@@ -6,6 +6,7 @@ mod layer;
 mod function;
 mod object;
 mod ty;
+mod id;
 pub fn routes(state: AppState) -> Router {
     Router::new()
         .route("/", get(root))
@@ -13,6 +14,7 @@ pub fn routes(state: AppState) -> Router {
         .nest("/function", function::routes(state.clone()))
         .nest("/object", object::routes(state.clone()))
         .nest("/type", ty::routes(state.clone()))
+        .nest("/id", id::routes(state.clone()))
 }
 
 pub async fn root() -> impl IntoResponse {
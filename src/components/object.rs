@@ -17,7 +17,7 @@ pub fn routes(state: AppState) -> Router {
         .with_state(state)
 }
 async fn history(State(state): State<AppState>, req: Validated<Json<HistoryRequest>>) -> impl IntoResponse {
-    let h = state.schema_manager.history(&req.name, DefinitionType::Object);
+    let h = state.schema_manager.history(&req, DefinitionType::Object);
     ApiResponse::ok("", Some(json!(h)))
 }
 
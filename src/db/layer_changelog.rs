@@ -0,0 +1,15 @@
+use sqlx::{PgPool, query, query_as};
+use crate::models::layer_changelog::LayerChangelog;
+use crate::models::requests::SchemaFamily;
+use crate::prelude::Res;
+
+pub async fn get(db: &PgPool, family: SchemaFamily, layer_id: i32) -> eyre::Result<Option<LayerChangelog>> {
+    Ok(query_as!(LayerChangelog, r#"select family as "family: SchemaFamily", layer_id, previous_layer_id, markdown, html from layer_changelog where family = $1 and layer_id = $2"#, family as SchemaFamily, layer_id).fetch_optional(db).await?)
+}
+/// replaces the changelog of the layer if there is one
+pub async fn upsert(db: &PgPool, changelog: LayerChangelog) -> Res {
+    query!("insert into layer_changelog (family, layer_id, previous_layer_id, markdown, html) values($1,$2,$3,$4,$5)
+            on conflict (family, layer_id) do update set previous_layer_id = excluded.previous_layer_id, markdown = excluded.markdown, html = excluded.html",
+        changelog.family as SchemaFamily, changelog.layer_id, changelog.previous_layer_id, changelog.markdown, changelog.html).execute(db).await?;
+    Ok(())
+}
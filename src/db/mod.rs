@@ -1 +1,3 @@
-pub mod tl_layer;
\ No newline at end of file
+pub mod tl_layer;
+pub mod layer_changelog;
+pub mod schema_file;
@@ -0,0 +1,12 @@
+use std::collections::HashMap;
+use sqlx::{PgPool, query};
+use crate::prelude::Res;
+
+/// blob shas of the files that were read, by path
+pub async fn get_all(db: &PgPool) -> eyre::Result<HashMap<String, String>> {
+    Ok(query!("select path, sha from schema_file").fetch_all(db).await?.into_iter().map(|f| (f.path, f.sha)).collect())
+}
+pub async fn upsert(db: &PgPool, path: &str, sha: &str) -> Res {
+    query!("insert into schema_file (path, sha) values($1,$2) on conflict (path) do update set sha = excluded.sha", path, sha).execute(db).await?;
+    Ok(())
+}
@@ -1,14 +1,18 @@
 use sqlx::{PgPool, query, query_as};
+use crate::models::requests::SchemaFamily;
 use crate::models::tl_layer::TlLayer;
 use crate::prelude::Res;
 
 pub async fn get_all(db: &PgPool) -> eyre::Result<Vec<TlLayer>> {
-    Ok(query_as!(TlLayer,"select * from tl_layer").fetch_all(db).await?)
+    Ok(query_as!(TlLayer, r#"select family as "family: SchemaFamily", layer_id, layer, release_date from tl_layer"#).fetch_all(db).await?)
 }
-pub async fn get_ids(db: &PgPool) -> eyre::Result<Vec<i32>> {
-    Ok(query!("select layer_id from tl_layer").fetch_all(db).await?.into_iter().map(|f| f.layer_id).collect())
+pub async fn get(db: &PgPool, family: SchemaFamily, layer_id: i32) -> eyre::Result<Option<TlLayer>> {
+    Ok(query_as!(TlLayer, r#"select family as "family: SchemaFamily", layer_id, layer, release_date from tl_layer where family = $1 and layer_id = $2"#, family as SchemaFamily, layer_id).fetch_optional(db).await?)
+}
+pub async fn get_ids(db: &PgPool, family: SchemaFamily) -> eyre::Result<Vec<i32>> {
+    Ok(query!("select layer_id from tl_layer where family = $1", family as SchemaFamily).fetch_all(db).await?.into_iter().map(|f| f.layer_id).collect())
 }
 pub async fn add(db: &PgPool, tl_layer: TlLayer) -> Res {
-    query!("insert into tl_layer values($1,$2,$3)",tl_layer.layer_id,tl_layer.layer,tl_layer.release_date).execute(db).await?;
+    query!("insert into tl_layer (family, layer_id, layer, release_date) values($1,$2,$3,$4)", tl_layer.family as SchemaFamily, tl_layer.layer_id, tl_layer.layer, tl_layer.release_date).execute(db).await?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,12 +1,16 @@
+use std::collections::{hash_map::Entry, HashMap};
 use std::sync::Arc;
 use chrono::{NaiveDate, NaiveDateTime};
 use clokwerk::{AsyncScheduler, TimeUnits};
 use reqwest::Client;
 use sqlx::PgPool;
-use crate::{continue_if, db};
+use crate::{continue_if, db, tl};
 use crate::models::github::{ GithubCommitDetail, GithubTree};
+use crate::models::requests::{SchemaFamily, SchemaSection};
 use crate::models::tl_layer::TlLayer;
 use crate::prelude::Res;
+use crate::tl::changelog::TlChangelog;
+use crate::tl::TlSchema;
 
 pub type Year = i32;
 pub type Month = u32;
@@ -26,7 +30,10 @@ pub async fn run_task(db: Arc<PgPool>) -> Res {
 }
 pub async fn run(db: Arc<PgPool>) -> Res {
     let client = Client::new();
-    let previous_layers = db::tl_layer::get_ids(&db).await?;
+    let mut previous_layers = db::tl_layer::get_ids(&db, SchemaFamily::Api).await?;
+    let read_files = db::schema_file::get_all(&db).await?;
+    //layers parsed during this run, `None` for the ones that can't be
+    let mut parsed = HashMap::new();
 
     let octo = octocrab::instance();
     let c = octo.repos("vrumger", "tl").list_commits().per_page(2).sha("master").send().await?;
@@ -53,39 +60,88 @@ pub async fn run(db: Arc<PgPool>) -> Res {
     let Ok(layer_list) = serde_json::from_str::<GithubTree>(&resp) else {
         return Err(eyre::Report::msg("failed to parse the layer_list"));
     };
-    for layer in layer_list.tree {
-        if !layer.path.ends_with(".tl") || layer.path.contains("unknown") {
-            continue;
-        }
-        let spl = layer.path.split(".").collect::<Vec<_>>()[0].trim();
-        let Ok(layer_id) = spl.parse::<u32>() else {
-            continue;
-        };
-        let layer_id = layer_id as i32;
+    for file in layer_list.tree {
+        continue_if!(!file.path.ends_with(".tl"));
+        //`158.tl`, only used when the content doesn't have a `// LAYER N` marker
+        let file_layer_id = file.path.split(".").collect::<Vec<_>>()[0].trim().parse::<u32>().ok().map(|id| id as i32);
+        continue_if!(!is_new(&file.path, &file.sha, file_layer_id, &previous_layers, &read_files));
 
-        continue_if!(previous_layers.contains(&layer_id));
-        log::trace!("fetching layer {layer_id}");
-
-        let (year, month) = find_commit_date(&client, layer.path).await?;
-        let date = NaiveDateTime::from(NaiveDate::from_ymd_opt(year, month, 1).unwrap());
-        let u = format!("https://raw.githubusercontent.com/vrumger/tl/{}/schemes/{layer_id}.tl",last_commit.sha);
-        println!("url: {u}");
+        let u = format!("https://raw.githubusercontent.com/vrumger/tl/{}/schemes/{}",last_commit.sha, file.path);
+        log::trace!("fetching {u}");
         let layer_content = client.get(u)
             .header(reqwest::header::USER_AGENT, USER_AGENT)
             .send().await?
             .text().await?;
 
+        let layer_id = tl::layer_metadata::read(&layer_content).layer.or(file_layer_id);
+        if layer_id.is_none() {
+            log::warn!("skipping {}, it doesn't have a layer marker or a numeric file name", file.path);
+        }
+        let Some(layer_id) = layer_id.filter(|id| !previous_layers.contains(id)) else {
+            db::schema_file::upsert(&db, &file.path, &file.sha).await?;
+            continue;
+        };
+
+        let (year, month) = find_commit_date(&client, file.path.clone()).await?;
+        let date = NaiveDateTime::from(NaiveDate::from_ymd_opt(year, month, 1).unwrap());
+
         let layer = TlLayer {
+            family: SchemaFamily::Api,
             layer_id,
             release_date: date,
             layer: layer_content,
         };
+        let schema = parse(TlLayer { layer: layer.layer.clone(), ..layer }).await;
         db::tl_layer::add(&db, layer).await?;
+        previous_layers.push(layer_id);
+        db::schema_file::upsert(&db, &file.path, &file.sha).await?;
+        parsed.insert(layer_id, schema);
+
+        store_changelog(&db, SchemaFamily::Api, layer_id, &previous_layers, &mut parsed).await?;
+        //a layer that arrives late sits between two stored ones, the next changelog was made against an older layer
+        if let Some(next) = previous_layers.iter().filter(|id| **id > layer_id).min() {
+            store_changelog(&db, SchemaFamily::Api, *next, &previous_layers, &mut parsed).await?;
+        }
     }
 
     Ok(())
 }
 
+/// whether a file of the schemes tree has to be downloaded. a file is read again when its blob changes,
+/// `158.tl` is skipped when layer 158 is stored whatever its blob
+fn is_new(path: &str, sha: &str, file_layer_id: Option<i32>, stored: &[i32], read_files: &HashMap<String, String>) -> bool {
+    !file_layer_id.is_some_and(|id| stored.contains(&id)) && read_files.get(path).is_none_or(|read| read != sha)
+}
+
+/// writes the changelog of `layer_id` against the stored layer before it, the first layer doesn't have one.
+/// layers missing from `parsed` are read from the db, each one is parsed once per run
+async fn store_changelog(db: &PgPool, family: SchemaFamily, layer_id: i32, layer_ids: &[i32], parsed: &mut HashMap<i32, Option<TlSchema>>) -> Res {
+    let Some(&previous) = layer_ids.iter().filter(|id| **id < layer_id).max() else {
+        return Ok(());
+    };
+    for id in [layer_id, previous] {
+        if let Entry::Vacant(e) = parsed.entry(id) {
+            let schema = match db::tl_layer::get(db, family, id).await? {
+                Some(layer) => parse(layer).await,
+                None => None,
+            };
+            e.insert(schema);
+        }
+    }
+    let (Some(Some(schema)), Some(Some(previous))) = (parsed.get(&layer_id), parsed.get(&previous)) else {
+        log::warn!("skipped the changelog of {family} layer {layer_id}, one of the layers could not be parsed");
+        return Ok(());
+    };
+    let changelog = TlChangelog::new(&tl::layer_diff::diff(previous, schema, SchemaSection::All));
+    db::layer_changelog::upsert(db, changelog.into()).await
+}
+
+/// parses a layer on the blocking pool, `None` when it can't be parsed.
+/// its definitions stay in the shared stores, the schema manager gets the same copies when it loads the layer
+async fn parse(layer: TlLayer) -> Option<TlSchema> {
+    tokio::task::spawn_blocking(move || tl::parse_schema(layer).ok()).await.ok().flatten()
+}
+
 async fn find_commit_date(client: &Client, path: String) -> eyre::Result<(Year, Month)> {
     let resp = client.get(format!("https://api.github.com/repos/vrumger/tl/commits?path=schemes/{path}"))
         .header(reqwest::header::USER_AGENT, USER_AGENT)
@@ -98,3 +154,18 @@ async fn find_commit_date(client: &Client, path: String) -> eyre::Result<(Year,
     };
     Ok(commit_detail.first().unwrap().date())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_files_that_were_read() {
+        let read = HashMap::from([("api.tl".to_owned(), "a1".to_owned()), ("158.tl".to_owned(), "b1".to_owned())]);
+        assert!(!is_new("api.tl", "a1", None, &[185], &read));
+        assert!(is_new("api.tl", "a2", None, &[185], &read));
+        assert!(is_new("tdesktop.tl", "c1", None, &[185], &read));
+        assert!(!is_new("158.tl", "b2", Some(158), &[158], &read));
+        assert!(is_new("159.tl", "d1", Some(159), &[158], &read));
+    }
+}
@@ -13,6 +13,7 @@ use crate::tl::schema_manager::SchemaManager;
 
 mod prelude;
 mod app_state;
+mod cli;
 mod components;
 mod github;
 mod models;
@@ -21,10 +22,15 @@ mod tl;
 
 #[tokio::main]
 async fn main() -> Res {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
     dotenv().ok();
     init_logger().await?;
     let (ms_url, ms_key) = (var("MS_PATH")?, var("MS_API_KEY")?);
     let db: Arc<PgPool> = Arc::new(PgPool::connect(&var("DATABASE_URL")?).await?);
+    sqlx::migrate!().run(&*db).await?;
 
     let r = Arc::new(AtomicBool::new(true));
     let running = r.clone();
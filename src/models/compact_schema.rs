@@ -1,23 +1,36 @@
 use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::models::requests::{SchemaFamily, SchemaSection};
 use crate::tl::tl_constructor::TlConstructor;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::TlDialect;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CompactTlDefinition {
     pub id: Uuid,
+    #[serde(default)]
+    pub family: SchemaFamily,
     pub layer_id: i32,
-    pub definition_id: String,
-    pub name: String,
-    pub namespace: String,
-    pub return_type: Option<String>,
+    pub definition_id: TlIdent,
+    pub name: TlIdent,
+    pub namespace: TlIdent,
+    pub return_type: Option<TlIdent>,
     pub definition_type: DefinitionType,
+    #[serde(default)]
+    pub section: SchemaSection,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub dialect: TlDialect,
 }
 #[derive(Serialize, Deserialize)]
 pub struct CompactTlConstructor {
-    pub id: String,
-    pub name: String,
+    pub id: TlIdent,
+    pub name: TlIdent,
     pub layer_id: i32,
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
@@ -39,12 +52,13 @@ impl Display for DefinitionType {
 
 #[derive(Serialize)]
 pub struct RefCompactTlConstructor<'a> {
-    pub id: &'a String,
-    pub name: &'a String,
+    pub id: &'a TlIdent,
+    pub name: &'a TlIdent,
+    pub description: &'a Option<String>,
 }
 
 impl<'a> From<&'a TlConstructor> for RefCompactTlConstructor<'a> {
     fn from(value: &'a TlConstructor) -> Self {
-        Self { id: &value.id, name: &value.name }
+        Self { id: &value.id, name: &value.name, description: &value.description }
     }
 }
\ No newline at end of file
@@ -0,0 +1,25 @@
+use crate::models::requests::SchemaFamily;
+use crate::tl::changelog::{TlChangelog, TlChangelogFormat};
+
+pub struct LayerChangelog {
+    pub family: SchemaFamily,
+    pub layer_id: i32,
+    pub previous_layer_id: i32,
+    pub markdown: String,
+    pub html: String,
+}
+
+impl LayerChangelog {
+    pub fn text(self, format: TlChangelogFormat) -> String {
+        match format {
+            TlChangelogFormat::Md => self.markdown,
+            TlChangelogFormat::Html => self.html,
+        }
+    }
+}
+
+impl From<TlChangelog> for LayerChangelog {
+    fn from(c: TlChangelog) -> Self {
+        Self { family: c.family, layer_id: c.layer_id, previous_layer_id: c.previous_layer_id, markdown: c.to_markdown(), html: c.to_html() }
+    }
+}
@@ -1,8 +1,10 @@
 use chrono::NaiveDate;
 use serde::Serialize;
+use crate::models::requests::SchemaFamily;
 
 #[derive(Serialize)]
 pub struct LayerReleaseDate {
+    pub family: SchemaFamily,
     pub layer_id: i32,
     pub release_date: NaiveDate,
 }
\ No newline at end of file
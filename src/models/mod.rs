@@ -2,5 +2,6 @@ pub mod github;
 pub mod tl_layer;
 pub mod compact_schema;
 pub mod layer_release_date;
+pub mod layer_changelog;
 pub mod requests;
 pub mod responses;
\ No newline at end of file
@@ -1,5 +1,9 @@
-use serde::Deserialize;
+use std::fmt::Display;
+use serde::{Deserialize, Serialize};
 use validify::Validify;
+use crate::tl::TlDialect;
+use crate::tl::changelog::TlChangelogFormat;
+use crate::tl::tl_graph::{TlDirection, TlGraphFormat, TlNodeKind};
 
 #[derive(Deserialize, Validify, Default)]
 #[serde(default)]
@@ -24,6 +28,13 @@ pub struct SearchLayerRequest {
     #[modify(trim)]
     #[serde(default)]
     pub highlight_postfix: Option<String>,
+    #[serde(default)]
+    pub family: SchemaFamily,
+    #[serde(default)]
+    pub section: SchemaSection,
+    /// only search layers of this dialect
+    #[serde(default)]
+    pub dialect: Option<TlDialect>,
 }
 #[derive(Deserialize, Validify, Default)]
 #[serde(default)]
@@ -37,6 +48,10 @@ pub struct GetByNameRequest {
     pub mode: FetchMode,
     #[validate(range(min = 1.0, max = 300.0, message = "limit must be between 1 and 300"))]
     pub limit: Option<usize>,
+    #[serde(default)]
+    pub family: SchemaFamily,
+    #[serde(default)]
+    pub section: SchemaSection,
 }
 
 #[derive(Deserialize, Validify, Default)]
@@ -45,6 +60,16 @@ pub struct HistoryRequest {
     #[validate(length(min = 3, max = 100, message = "length must be between 3 and 100"))]
     #[modify(trim)]
     pub name: String,
+    #[serde(default)]
+    pub family: SchemaFamily,
+    #[serde(default)]
+    pub section: SchemaSection,
+    /// telegram layers and tdlib versions have separate histories
+    #[serde(default)]
+    pub dialect: TlDialect,
+    /// leave out additive changes
+    #[serde(default)]
+    pub breaking_only: bool,
 }
 
 #[derive(Deserialize, Default)]
@@ -54,6 +79,121 @@ pub enum FetchMode {
     Compact,
     Full,
 }
+
+/// which part of a layer to look at
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaSection {
+    #[default]
+    Api,
+    Mtproto,
+    All,
+}
+
+impl Display for SchemaSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            SchemaSection::Api => "api",
+            SchemaSection::Mtproto => "mtproto",
+            SchemaSection::All => "all",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// independent schemas, each one has its own layer numbers
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "schema_family", rename_all = "lowercase")]
+pub enum SchemaFamily {
+    /// `api.tl`, the main application api
+    #[default]
+    Api,
+    /// a standalone `mtproto.tl`
+    Mtproto,
+    /// `secret_api.tl`, end-to-end encrypted chats
+    Secret,
+    /// forks and anything else
+    Custom,
+}
+
+impl Display for SchemaFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            SchemaFamily::Api => "api",
+            SchemaFamily::Mtproto => "mtproto",
+            SchemaFamily::Secret => "secret",
+            SchemaFamily::Custom => "custom",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct LayerQuery {
+    pub family: SchemaFamily,
+    pub section: SchemaSection,
+}
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct FamilyQuery {
+    pub family: SchemaFamily,
+}
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct LintQuery {
+    pub family: SchemaFamily,
+    pub section: SchemaSection,
+    /// comma separated check names, every check runs if it's missing
+    pub checks: Option<String>,
+}
+#[derive(Deserialize)]
+pub struct LayerDiffQuery {
+    pub from: u32,
+    pub to: u32,
+    #[serde(default)]
+    pub family: SchemaFamily,
+    #[serde(default)]
+    pub section: SchemaSection,
+    /// leave out additive changes
+    #[serde(default)]
+    pub breaking_only: bool,
+}
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ChangelogQuery {
+    pub family: SchemaFamily,
+    pub format: TlChangelogFormat,
+}
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct GraphQuery {
+    pub family: SchemaFamily,
+    pub section: SchemaSection,
+    /// needed when a type, a constructor or a function share the name
+    pub kind: Option<TlNodeKind>,
+    /// edges to follow from the node, all of them if it's missing
+    pub depth: Option<usize>,
+    /// only walk through definitions of this namespace, `""` for the ones without a namespace
+    pub namespace: Option<String>,
+    /// leave out primitives like `int`, `string` and `Bool`
+    pub collapse_primitives: bool,
+}
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct GraphExportQuery {
+    pub family: SchemaFamily,
+    pub section: SchemaSection,
+    /// name of the node to start from
+    pub root: String,
+    pub kind: Option<TlNodeKind>,
+    pub depth: Option<usize>,
+    pub direction: TlDirection,
+    pub format: TlGraphFormat,
+    pub namespace: Option<String>,
+    pub collapse_primitives: bool,
+}
 #[derive(Deserialize, Validify)]
 pub struct GetNamespaceRequest {
     #[validate(range(min = 1.0, max = 1000.0, message = "id must be between 1 and 1000"))]
@@ -61,5 +201,39 @@ pub struct GetNamespaceRequest {
     #[validate(length(min = 3, max = 100, message = "length must be between 3 and 100"))]
     #[modify(trim)]
     pub namespace: String,
+    #[serde(default)]
+    pub family: SchemaFamily,
+    #[serde(default)]
+    pub section: SchemaSection,
 }
 
+#[derive(Deserialize)]
+pub struct EncodeRequest {
+    /// objects name their constructor or function with `_`
+    pub value: serde_json::Value,
+    /// type of `value` like `Vector<User>` or `%Message`, a boxed object or a function call if it's missing
+    #[serde(rename = "type")]
+    pub _type: Option<String>,
+}
+#[derive(Deserialize)]
+pub struct DecodeRequest {
+    /// hex or base64, whitespace is ignored
+    pub payload: String,
+    /// type of the payload like `Vector<User>` or `%Message`, a boxed object or a function call if it's missing
+    #[serde(rename = "type")]
+    pub _type: Option<String>,
+}
+#[derive(Deserialize)]
+pub struct InspectRequest {
+    /// hex or base64, whitespace is ignored
+    pub payload: String,
+    /// only try the layers of this family
+    #[serde(default)]
+    pub family: Option<SchemaFamily>,
+}
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct IdQuery {
+    /// only look in layers of this family
+    pub family: Option<SchemaFamily>,
+}
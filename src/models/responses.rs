@@ -1,20 +1,35 @@
 use std::fmt::Display;
+use chrono::NaiveDateTime;
 use meilisearch_sdk::search::{SearchResult};
 use serde::{Serialize};
 use serde_json::{Map, Value};
 use crate::models::compact_schema::{CompactTlConstructor, CompactTlDefinition, DefinitionType, RefCompactTlConstructor};
+use crate::models::requests::{SchemaFamily, SchemaSection};
 use crate::tl::tl_constructor::TlConstructor;
+use crate::tl::impact::Classified;
+use crate::tl::layer_metadata::TlLayerMetadata;
+use crate::tl::tl_diagnostic::TlDiagnostic;
+use crate::tl::tl_flag::{TlFlagBit, TlFlagField};
 use crate::tl::tl_function::TlFunction;
+use crate::tl::tl_shared::TlShared;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_type::TlCommonParameter;
+use crate::tl::tl_type_ref::TlTypeRef;
+use crate::tl::{TlDialect, TlSection};
 
 #[derive(Serialize)]
 pub struct CompactTlDefinitionResponse {
     pub ranking_score: f64,
+    pub family: SchemaFamily,
     pub layer_id: i32,
-    pub definition_id: String,
-    pub name: String,
-    pub namespace: String,
-    pub return_type: Option<String>,
+    pub definition_id: TlIdent,
+    pub name: TlIdent,
+    pub namespace: TlIdent,
+    pub return_type: Option<TlIdent>,
     pub definition_type: DefinitionType,
+    pub section: SchemaSection,
+    pub dialect: TlDialect,
+    pub description: Option<String>,
     pub formated_result: Option<Map<String, Value>>,
 }
 impl CompactTlDefinitionResponse {
@@ -22,12 +37,16 @@ impl CompactTlDefinitionResponse {
         let formated_result = if highlight { value.formatted_result } else { None };
         Self {
             ranking_score: value.ranking_score.unwrap_or(0.0),
+            family: value.result.family,
             name: value.result.name,
             return_type: value.result.return_type,
             namespace: value.result.namespace,
             definition_id: value.result.definition_id,
             layer_id: value.result.layer_id,
             definition_type: value.result.definition_type,
+            section: value.result.section,
+            dialect: value.result.dialect,
+            description: value.result.description,
             formated_result,
         }
     }
@@ -45,8 +64,10 @@ impl Display for SearchResponse {
 }
 #[derive(Serialize, Debug, Clone)]
 pub struct GetFunction<'a> {
-    pub function: &'a TlFunction,
+    pub function: &'a TlShared<TlFunction>,
     pub layer_id: u32,
+    /// the definition as a `.tl` line
+    pub tl: String,
 }
 
 #[derive(Serialize)]
@@ -77,15 +98,17 @@ impl<'a> GetObjectResponse<'a> {
 }
 #[derive(Serialize, Debug, Clone)]
 pub struct GetObject<'a> {
-    pub obj: &'a TlConstructor,
+    pub obj: &'a TlShared<TlConstructor>,
     pub usages: Vec<ObjectUsage<'a>>,
     pub layer_id: u32,
+    /// the definition as a `.tl` line
+    pub tl: String,
 }
 #[derive(Serialize, Debug, Clone)]
 pub enum ObjectUsage<'a> {
-    Param { tl_function: &'a TlFunction, is_inner: bool },
-    ReturnType { tl_function: &'a TlFunction, is_inner: bool },
-    ViaNamespace { tl_function: &'a TlFunction, is_inner: bool },
+    Param { tl_function: &'a TlShared<TlFunction>, is_inner: bool },
+    ReturnType { tl_function: &'a TlShared<TlFunction>, is_inner: bool },
+    ViaNamespace { tl_function: &'a TlShared<TlFunction>, is_inner: bool },
 }
 
 #[derive(Serialize)]
@@ -97,19 +120,24 @@ pub enum HistoryResponse<'a> {
 
 #[derive(Serialize)]
 pub struct FunctionHistoryResponse<'a> {
-    pub history: Vec<FunctionHistory<'a>>,
+    pub history: Vec<Classified<FunctionHistory<'a>>>,
     pub last_definition: GetFunction<'a>,
 }
 #[derive(Serialize)]
 pub enum FunctionHistory<'a> {
     AddedIn { layer_id: u32 },
     DeletedIn { layer_id: u32 },
-    ParamAdded { layer_id: u32, name: &'a str, param_type: &'a str },
-    ParamChanged { layer_id: u32, diff: Vec<Diff<'a>>, name: &'a String },
-    ParamDeleted { layer_id: u32, name: &'a str },
-    ReturnTypeChanged { layer_id: u32, before: &'a str, after: &'a str },
-}
-#[derive(Serialize)]
+    /// the definition lives on under another name, matched by [`matcher`](crate::tl::matcher)
+    Renamed { layer_id: u32, from: &'a TlIdent, to: &'a TlIdent },
+    IdChanged { layer_id: u32, before: &'a TlIdent, after: &'a TlIdent },
+    ParamAdded { layer_id: u32, name: &'a str, param_type: &'a TlTypeRef },
+    ParamChanged { layer_id: u32, diff: Vec<Diff<'a>>, name: &'a TlIdent },
+    ParamDeleted { layer_id: u32, name: &'a str, param_type: &'a TlTypeRef },
+    /// parameters kept from the layer before are in a different order
+    ParamsReordered { layer_id: u32 },
+    ReturnTypeChanged { layer_id: u32, before: &'a TlTypeRef, after: &'a TlTypeRef },
+}
+#[derive(Serialize, Debug)]
 pub struct Diff<'a> {
     pub from: String,
     pub to: String,
@@ -117,7 +145,7 @@ pub struct Diff<'a> {
 }
 #[derive(Serialize)]
 pub struct ObjectHistoryResponse<'a> {
-    pub history: Vec<ObjectHistory<'a>>,
+    pub history: Vec<Classified<ObjectHistory<'a>>>,
     pub last_definition: GetObject<'a>,
 }
 
@@ -125,22 +153,27 @@ pub struct ObjectHistoryResponse<'a> {
 pub enum ObjectHistory<'a> {
     AddedIn { layer_id: u32 },
     DeletedIn { layer_id: u32 },
-    ParamAdded { layer_id: u32, name: &'a str, param_type: &'a str },
-    ParamChanged { layer_id: u32, diff: Vec<Diff<'a>>, name: &'a String },
-    ParamDeleted { layer_id: u32, name: &'a str },
+    /// the definition lives on under another name, matched by [`matcher`](crate::tl::matcher)
+    Renamed { layer_id: u32, from: &'a TlIdent, to: &'a TlIdent },
+    IdChanged { layer_id: u32, before: &'a TlIdent, after: &'a TlIdent },
+    ParamAdded { layer_id: u32, name: &'a str, param_type: &'a TlTypeRef },
+    ParamChanged { layer_id: u32, diff: Vec<Diff<'a>>, name: &'a TlIdent },
+    ParamDeleted { layer_id: u32, name: &'a str, param_type: &'a TlTypeRef },
+    /// parameters kept from the layer before are in a different order
+    ParamsReordered { layer_id: u32 },
 }
 
 
 #[derive(Serialize)]
 pub struct Namespace<'a> {
     pub layer_id: u32,
-    pub function_ns: Vec<&'a String>,
-    pub object_ns: Vec<&'a String>,
+    pub function_ns: Vec<&'a TlIdent>,
+    pub object_ns: Vec<&'a TlIdent>,
 }
 #[derive(Serialize)]
 pub struct TypeResponse<'a> {
     pub layer_id: i32,
-    pub types: Vec<&'a String>,
+    pub types: Vec<&'a TlIdent>,
 }
 
 #[derive(Serialize)]
@@ -152,10 +185,69 @@ pub enum GetTypeResponse<'a> {
 #[derive(Serialize)]
 pub struct GetTypeFull<'a> {
     pub layer_id: i32,
-    pub objects: &'a Vec<TlConstructor>,
+    pub description: &'a Option<String>,
+    pub objects: &'a Vec<TlShared<TlConstructor>>,
+    pub common_parameters: &'a Vec<TlCommonParameter>,
+    /// every constructor of the type as `.tl` text
+    pub tl: String,
 }
 #[derive(Serialize)]
 pub struct GetTypeCompact<'a> {
     pub layer_id: i32,
+    pub description: &'a Option<String>,
     pub objects: Vec<RefCompactTlConstructor<'a>>,
-}
\ No newline at end of file
+    pub common_parameters: &'a Vec<TlCommonParameter>,
+}
+#[derive(Serialize)]
+pub struct GetLayerResponse<'a> {
+    pub family: SchemaFamily,
+    pub layer_id: i32,
+    pub dialect: TlDialect,
+    pub release_date: NaiveDateTime,
+    pub metadata: &'a TlLayerMetadata,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api: Option<&'a TlSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtproto: Option<&'a TlSection>,
+    pub diagnostics: &'a Vec<TlDiagnostic>,
+}
+
+#[derive(Serialize)]
+pub struct FlagLayoutResponse<'a> {
+    pub layer_id: i32,
+    pub name: &'a str,
+    pub definition_type: DefinitionType,
+    pub fields: Vec<FlagFieldLayout<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct FlagFieldLayout<'a> {
+    pub name: &'a str,
+    pub bits: &'a Vec<TlFlagBit>,
+    pub unused_bits: Vec<u8>,
+    pub groups: Vec<&'a TlFlagBit>,
+}
+
+impl<'a> From<&'a TlFlagField> for FlagFieldLayout<'a> {
+    fn from(value: &'a TlFlagField) -> Self {
+        Self { name: &value.name, bits: &value.bits, unused_bits: value.unused_bits(), groups: value.groups() }
+    }
+}
+
+/// a definition found by its id and the layers that contain it
+#[derive(Serialize)]
+pub struct IdHit<'a> {
+    pub id: &'a TlIdent,
+    pub name: &'a TlIdent,
+    pub family: SchemaFamily,
+    pub section: SchemaSection,
+    pub definition_type: DefinitionType,
+    /// type of a constructor
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub _type: Option<&'a TlIdent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_type: Option<&'a TlTypeRef>,
+    /// the definition as a `.tl` line
+    pub tl: String,
+    pub layers: Vec<i32>,
+}
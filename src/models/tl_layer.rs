@@ -1,6 +1,8 @@
 use chrono::{NaiveDateTime};
+use crate::models::requests::SchemaFamily;
 
 pub struct TlLayer {
+    pub family: SchemaFamily,
     pub layer_id: i32,
     pub layer: String,
     pub release_date: NaiveDateTime,
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use serde::{Deserialize, Serialize};
+use crate::models::requests::SchemaFamily;
+use crate::tl::impact::TlImpact;
+use crate::tl::layer_diff::{TlChange, TlDefinitionDiff, TlDiffKind, TlLayerDiff};
+
+/// heading of the definitions without a namespace
+const GLOBAL_NAMESPACE: &str = "Global";
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TlChangelogFormat {
+    #[default]
+    Md,
+    Html,
+}
+
+/// release notes of a layer compared with the layer stored before it, grouped by namespace
+#[derive(Debug)]
+pub struct TlChangelog {
+    pub family: SchemaFamily,
+    pub layer_id: i32,
+    pub previous_layer_id: i32,
+    namespaces: BTreeMap<String, NamespaceNotes>,
+}
+
+#[derive(Debug, Default)]
+struct NamespaceNotes {
+    new_methods: Vec<String>,
+    new_constructors: Vec<String>,
+    changed: Vec<ChangedNote>,
+    removed: Vec<String>,
+}
+
+#[derive(Debug)]
+struct ChangedNote {
+    name: String,
+    impact: TlImpact,
+    /// one line per change, markdown with only inline code
+    details: Vec<String>,
+}
+
+impl TlChangelog {
+    pub fn new(diff: &TlLayerDiff) -> Self {
+        let mut namespaces = BTreeMap::<String, NamespaceNotes>::new();
+        for (d, is_function) in diff.constructors.iter().map(|d| (d, false)).chain(diff.functions.iter().map(|d| (d, true))) {
+            let namespace = d.name.split_once('.').map(|(ns, _)| ns).unwrap_or(GLOBAL_NAMESPACE);
+            let notes = namespaces.entry(namespace.to_owned()).or_default();
+            match d.kind {
+                TlDiffKind::Added if is_function => notes.new_methods.push(format!("`{}`", d.after.as_deref().unwrap_or_default())),
+                TlDiffKind::Added => notes.new_constructors.push(format!("`{}`", d.after.as_deref().unwrap_or_default())),
+                TlDiffKind::Removed => notes.removed.push(format!("{} `{}`", if is_function { "method" } else { "constructor" }, d.name)),
+                TlDiffKind::Changed | TlDiffKind::Renamed => notes.changed.push(ChangedNote::new(d)),
+            }
+        }
+        Self { family: diff.family, layer_id: diff.to, previous_layer_id: diff.from, namespaces }
+    }
+
+    pub fn render(&self, format: TlChangelogFormat) -> String {
+        match format {
+            TlChangelogFormat::Md => self.to_markdown(),
+            TlChangelogFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Layer {}\n\nChanges since layer {} of the {} schema.\n", self.layer_id, self.previous_layer_id, self.family);
+        if self.namespaces.is_empty() {
+            out.push_str("\nNothing changed.\n");
+        }
+        for (namespace, notes) in &self.namespaces {
+            let _ = writeln!(out, "\n## {namespace}");
+            for (title, lines) in notes.added() {
+                markdown_list(&mut out, title, lines);
+            }
+            if !notes.changed.is_empty() {
+                out.push_str("\n### Changed\n\n");
+            }
+            for note in &notes.changed {
+                let _ = writeln!(out, "- `{}`{}", note.name, impact_label(note.impact));
+                for detail in &note.details {
+                    let _ = writeln!(out, "  - {detail}");
+                }
+            }
+            if !notes.removed.is_empty() {
+                markdown_list(&mut out, "Removed", &notes.removed);
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = format!("<h1>Layer {}</h1>\n<p>Changes since layer {} of the {} schema.</p>\n", self.layer_id, self.previous_layer_id, self.family);
+        if self.namespaces.is_empty() {
+            out.push_str("<p>Nothing changed.</p>\n");
+        }
+        for (namespace, notes) in &self.namespaces {
+            let _ = writeln!(out, "<h2>{}</h2>", escape(namespace));
+            for (title, lines) in notes.added() {
+                html_list(&mut out, title, lines);
+            }
+            if !notes.changed.is_empty() {
+                out.push_str("<h3>Changed</h3>\n<ul>\n");
+            }
+            for note in &notes.changed {
+                let _ = write!(out, "<li><code>{}</code>{}\n<ul>", escape(&note.name), inline_html(&impact_label(note.impact)));
+                for detail in &note.details {
+                    let _ = write!(out, "<li>{}</li>", inline_html(detail));
+                }
+                out.push_str("</ul></li>\n");
+            }
+            if !notes.changed.is_empty() {
+                out.push_str("</ul>\n");
+            }
+            if !notes.removed.is_empty() {
+                html_list(&mut out, "Removed", &notes.removed);
+            }
+        }
+        out
+    }
+}
+
+impl NamespaceNotes {
+    fn added(&self) -> impl Iterator<Item=(&str, &Vec<String>)> {
+        [("New methods", &self.new_methods), ("New constructors", &self.new_constructors)]
+            .into_iter()
+            .filter(|(_, lines)| !lines.is_empty())
+    }
+}
+
+impl ChangedNote {
+    fn new(d: &TlDefinitionDiff) -> Self {
+        let details = d.changes.iter().flat_map(|c| match &c.change {
+            TlChange::Renamed { before, .. } => vec![format!("renamed from `{before}`")],
+            TlChange::IdChanged { before, after } => vec![format!("id `{before}` is now `{after}`")],
+            TlChange::TypeChanged { before, after } => vec![format!("moved from `{before}` to `{after}`")],
+            TlChange::ReturnTypeChanged { before, after } => vec![format!("returns `{after}` instead of `{before}`")],
+            TlChange::ParamAdded { name, param_type } => vec![format!("added `{name}:{param_type}`")],
+            TlChange::ParamDeleted { name, param_type } => vec![format!("removed `{name}:{param_type}`")],
+            TlChange::ParamChanged { name, diff } => diff.iter().map(|d| format!("`{name}` is now `{}` instead of `{}`", d.to, d.from)).collect(),
+            TlChange::ParamsReordered => vec!["parameters were reordered".to_owned()],
+            TlChange::DescriptionChanged => vec!["documentation was updated".to_owned()],
+        }).collect();
+        Self { name: d.name.to_string(), impact: d.impact, details }
+    }
+}
+
+fn markdown_list(out: &mut String, title: &str, lines: &[String]) {
+    let _ = writeln!(out, "\n### {title}\n");
+    for line in lines {
+        let _ = writeln!(out, "- {line}");
+    }
+}
+
+fn html_list(out: &mut String, title: &str, lines: &[String]) {
+    let _ = writeln!(out, "<h3>{title}</h3>\n<ul>");
+    for line in lines {
+        let _ = writeln!(out, "<li>{}</li>", inline_html(line));
+    }
+    out.push_str("</ul>\n");
+}
+
+fn impact_label(impact: TlImpact) -> String {
+    match impact {
+        TlImpact::Additive => String::new(),
+        TlImpact::SourceBreaking => " (source breaking)".to_owned(),
+        TlImpact::WireBreaking => " (wire breaking)".to_owned(),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// escapes the text and turns `code` spans into `<code>` tags
+fn inline_html(s: &str) -> String {
+    s.split('`')
+        .enumerate()
+        .map(|(i, part)| if i % 2 == 1 { format!("<code>{}</code>", escape(part)) } else { escape(part) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::requests::SchemaSection;
+    use crate::models::tl_layer::TlLayer;
+    use crate::tl::{self, layer_diff, TlSchema};
+
+    fn parse(layer_id: i32, src: &str) -> TlSchema {
+        tl::parse_schema(TlLayer { family: SchemaFamily::Api, layer_id, layer: src.to_owned(), release_date: Default::default() }).unwrap()
+    }
+
+    fn changelog() -> TlChangelog {
+        let before = parse(1, include_str!("../../tests/fixtures/changelog_1.tl"));
+        let after = parse(2, include_str!("../../tests/fixtures/changelog_2.tl"));
+        TlChangelog::new(&layer_diff::diff(&before, &after, SchemaSection::All))
+    }
+
+    #[test]
+    fn renders_markdown() {
+        assert_eq!(changelog().render(TlChangelogFormat::Md), r"# Layer 2
+
+Changes since layer 1 of the api schema.
+
+## Global
+
+### New constructors
+
+- `inputPeerSelf#7da07ec9 = InputPeer;`
+
+### Changed
+
+- `chatProfilePhoto` (wire breaking)
+  - renamed from `chatPhoto`
+  - id `1c6e1c11` is now `1c6e1c12`
+
+## help
+
+### Removed
+
+- method `help.getConfig`
+
+## messages
+
+### New methods
+
+- `messages.getDialogs#a0f4cb4f limit:int = Bool;`
+
+### Changed
+
+- `messages.getChats` (wire breaking)
+  - `id` is now `Vector<int>` instead of `Vector<long>`
+");
+    }
+
+    #[test]
+    fn renders_html() {
+        assert_eq!(changelog().render(TlChangelogFormat::Html), r"<h1>Layer 2</h1>
+<p>Changes since layer 1 of the api schema.</p>
+<h2>Global</h2>
+<h3>New constructors</h3>
+<ul>
+<li><code>inputPeerSelf#7da07ec9 = InputPeer;</code></li>
+</ul>
+<h3>Changed</h3>
+<ul>
+<li><code>chatProfilePhoto</code> (wire breaking)
+<ul><li>renamed from <code>chatPhoto</code></li><li>id <code>1c6e1c11</code> is now <code>1c6e1c12</code></li></ul></li>
+</ul>
+<h2>help</h2>
+<h3>Removed</h3>
+<ul>
+<li>method <code>help.getConfig</code></li>
+</ul>
+<h2>messages</h2>
+<h3>New methods</h3>
+<ul>
+<li><code>messages.getDialogs#a0f4cb4f limit:int = Bool;</code></li>
+</ul>
+<h3>Changed</h3>
+<ul>
+<li><code>messages.getChats</code> (wire breaking)
+<ul><li><code>id</code> is now <code>Vector&lt;int&gt;</code> instead of <code>Vector&lt;long&gt;</code></li></ul></li>
+</ul>
+");
+    }
+}
@@ -0,0 +1,724 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use eyre::{bail, eyre};
+use base64::{alphabet, engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig}, Engine};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::models::requests::SchemaSection;
+use crate::tl::{constructor_id, TlSchema};
+use crate::tl::tl_constructor::TlConstructor;
+use crate::tl::tl_function::TlFunction;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_type::TlType;
+use crate::tl::tl_type_ref::TlTypeRef;
+
+pub const VECTOR_ID: u32 = 0x1cb5c415;
+pub const BOOL_TRUE_ID: u32 = 0x997275b5;
+pub const BOOL_FALSE_ID: u32 = 0xbc799737;
+/// key of a json object that names its constructor or function
+pub const NAME_KEY: &str = "_";
+/// objects nested deeper are rejected, a crafted payload would overflow the stack otherwise
+pub const MAX_DEPTH: usize = 64;
+/// padded or not, captures come both ways
+const BASE64: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent));
+
+/// a constructor or a function of the layer
+#[derive(Clone, Copy)]
+pub enum Definition<'a> {
+    Constructor(&'a TlType, &'a TlConstructor),
+    Function(&'a TlFunction),
+}
+
+impl<'a> Definition<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            Definition::Constructor(_, c) => &c.name,
+            Definition::Function(f) => &f.name,
+        }
+    }
+
+    pub fn parameters(&self) -> &'a [TlParameter] {
+        match self {
+            Definition::Constructor(_, c) => &c.parameters,
+            Definition::Function(f) => &f.parameters,
+        }
+    }
+
+    fn type_params(&self) -> &'a [TlIdent] {
+        match self {
+            Definition::Constructor(_, c) => &c.type_params,
+            Definition::Function(f) => &f.type_params,
+        }
+    }
+
+    /// name of the type a constructor belongs to, `None` for functions
+    pub fn type_name(&self) -> Option<&'a str> {
+        match self {
+            Definition::Constructor(t, _) => Some(&t.name),
+            Definition::Function(_) => None,
+        }
+    }
+}
+
+/// converts json values to tl binary and back using the definitions of one layer.
+/// objects carry their constructor or function name under `_`, `long`s are numbers (strings are accepted too),
+/// `bytes`, `int128` and `int256` are hex strings of the bytes as they are on the wire
+pub struct TlCodec<'a> {
+    ids: HashMap<u32, Definition<'a>>,
+    names: HashMap<&'a str, (u32, Definition<'a>)>,
+    /// mtproto and api can both define a type, `Message` for example
+    types: HashMap<&'a str, Vec<&'a TlType>>,
+}
+
+impl<'a> TlCodec<'a> {
+    /// every definition of the layer is used, mtproto and api alike
+    pub fn new(schema: &'a TlSchema) -> Self {
+        let mut codec = Self { ids: HashMap::new(), names: HashMap::new(), types: HashMap::new() };
+        for tl_type in schema.objects(SchemaSection::All) {
+            codec.types.entry(&tl_type.name).or_default().push(tl_type);
+            for c in &tl_type.constructors {
+                codec.add(&c.id, Definition::Constructor(tl_type, c));
+            }
+        }
+        for f in schema.functions(SchemaSection::All).flat_map(|(_, f)| f) {
+            codec.add(&f.id, Definition::Function(f));
+        }
+        codec
+    }
+
+    fn add(&mut self, id: &str, d: Definition<'a>) {
+        let id = constructor_id::parse_id(id).unwrap_or_default();
+        self.ids.entry(id).or_insert(d);
+        self.names.entry(d.name()).or_insert((id, d));
+    }
+
+    pub fn definition(&self, id: u32) -> Option<Definition<'a>> {
+        self.ids.get(&id).copied()
+    }
+
+    /// the constructor a bare `name` refers to, `user` or `%User`. a bare type has a single constructor,
+    /// when a name has several `constructor` picks one of them, and it has to be that one otherwise
+    fn bare_constructor(&self, name: &str, constructor: Option<&str>) -> Result<Definition<'a>, String> {
+        let single = |d: Definition<'a>| match constructor {
+            Some(c) if c != d.name() => Err(format!("`%{name}` is always `{}`, found `{c}`", d.name())),
+            _ => Ok(d),
+        };
+        if let Some((_, d @ Definition::Constructor(..))) = self.names.get(name) {
+            return single(*d);
+        }
+        let types = self.types.get(name).ok_or_else(|| format!("unknown type `{name}`"))?;
+        let constructors = || types.iter().flat_map(|t| t.constructors.iter().map(move |c| Definition::Constructor(t, c)));
+        if let Some(t) = types.iter().find(|t| t.constructors.len() == 1) {
+            return single(Definition::Constructor(t, &t.constructors[0]));
+        }
+        match constructor {
+            Some(c) => constructors().find(|d| d.name() == c).ok_or_else(|| format!("`{c}` isn't a constructor of `{name}`")),
+            None => Err(format!("`%{name}` has several constructors, a bare value can't tell which one it is")),
+        }
+    }
+
+    /// `value` as `_type`, a boxed object or function call if it's `None`
+    pub fn encode(&self, value: &Value, _type: Option<&TlTypeRef>) -> eyre::Result<Vec<u8>> {
+        let mut writer = Writer { codec: self, out: vec![], path: vec![], depth: 0 };
+        match _type {
+            Some(t) => writer.value(value, t, &[])?,
+            None => writer.boxed_any(value)?,
+        }
+        Ok(writer.out)
+    }
+
+    /// reads a `_type` from `bytes`, a boxed object or function call if it's `None`. every byte has to be used
+    pub fn decode(&self, bytes: &[u8], _type: Option<&TlTypeRef>) -> eyre::Result<Value> {
+        let mut reader = Reader { codec: self, bytes, pos: 0, path: vec![], depth: 0, lenient: false, failure: None };
+        let value = match _type {
+            Some(t) => reader.value(t, &[])?,
+            None => reader.boxed_any()?,
+        };
+        if reader.pos != bytes.len() {
+            bail!("found {} extra bytes after the value", bytes.len() - reader.pos);
+        }
+        Ok(value)
+    }
+
+    /// reads a boxed object or function call as far as it goes, trailing bytes and truncation don't fail it
+    pub fn inspect(&self, bytes: &[u8]) -> TlInspection {
+        let mut reader = Reader { codec: self, bytes, pos: 0, path: vec![], depth: 0, lenient: true, failure: None };
+        match reader.boxed_any() {
+            Ok(value) => TlInspection {
+                value: Some(value),
+                consumed: reader.pos,
+                trailing: if reader.failure.is_none() { bytes.len() - reader.pos } else { 0 },
+                error: reader.failure,
+            },
+            Err(e) => TlInspection { value: None, consumed: 0, trailing: 0, error: Some(e.to_string()) },
+        }
+    }
+}
+
+/// result of [`TlCodec::inspect`]
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TlInspection {
+    /// what could be read, partial if `error` is set
+    pub value: Option<Value>,
+    /// bytes read
+    pub consumed: usize,
+    /// bytes left after a complete value
+    pub trailing: usize,
+    /// where reading stopped
+    pub error: Option<String>,
+}
+
+impl TlInspection {
+    /// the value was read completely and used every byte
+    pub fn is_clean(&self) -> bool {
+        self.error.is_none() && self.trailing == 0
+    }
+}
+
+struct Writer<'c, 'a> {
+    codec: &'c TlCodec<'a>,
+    out: Vec<u8>,
+    /// fields and vector indexes leading to the current value, for error messages
+    path: Vec<String>,
+    /// objects around the current value
+    depth: usize,
+}
+
+impl<'a> Writer<'_, 'a> {
+    fn error<M: Display>(&self, message: M) -> eyre::Report {
+        match self.path.is_empty() {
+            true => eyre!("{message}"),
+            false => eyre!("`{}`: {message}", self.path.join(".")),
+        }
+    }
+
+    fn value(&mut self, v: &Value, t: &TlTypeRef, type_params: &[TlIdent]) -> eyre::Result<()> {
+        match t {
+            TlTypeRef::Flag { inner, .. } => self.value(v, inner, type_params),
+            TlTypeRef::Nat => {
+                let n = integer(v).and_then(|n| u32::try_from(n).ok()).ok_or_else(|| self.error("expected a positive integer"))?;
+                self.out.extend(n.to_le_bytes());
+                Ok(())
+            }
+            TlTypeRef::Generic { .. } => self.boxed_any(v),
+            TlTypeRef::Boxed { name } if type_params.contains(name) || name == "Object" => self.boxed_any(v),
+            TlTypeRef::Boxed { name } if name == "Bool" => {
+                let b = v.as_bool().ok_or_else(|| self.error("expected a boolean"))?;
+                self.out.extend(if b { BOOL_TRUE_ID } else { BOOL_FALSE_ID }.to_le_bytes());
+                Ok(())
+            }
+            TlTypeRef::Boxed { name } => {
+                let (id, d) = self.named(v)?;
+                if d.type_name() != Some(name.as_str()) {
+                    return Err(self.error(format!("`{}` isn't a constructor of `{name}`", d.name())));
+                }
+                self.out.extend(id.to_le_bytes());
+                self.fields(v, d)
+            }
+            TlTypeRef::Vector { bare, inner } => {
+                let Value::Array(items) = v else {
+                    return Err(self.error("expected an array"));
+                };
+                if !bare {
+                    self.out.extend(VECTOR_ID.to_le_bytes());
+                }
+                self.out.extend((items.len() as u32).to_le_bytes());
+                for (i, item) in items.iter().enumerate() {
+                    self.path.push(i.to_string());
+                    self.value(item, inner, type_params)?;
+                    self.path.pop();
+                }
+                Ok(())
+            }
+            TlTypeRef::Bare { name } => self.bare(v, name),
+        }
+    }
+
+    fn bare(&mut self, v: &Value, name: &str) -> eyre::Result<()> {
+        match name {
+            "int" => {
+                let n = integer(v).filter(|n| *n >= i32::MIN as i64 && *n <= u32::MAX as i64).ok_or_else(|| self.error("expected a 32 bit integer"))?;
+                self.out.extend((n as u32).to_le_bytes());
+            }
+            "long" => {
+                let n = integer(v).ok_or_else(|| self.error("expected a 64 bit integer"))?;
+                self.out.extend(n.to_le_bytes());
+            }
+            "double" => {
+                let n = v.as_f64().ok_or_else(|| self.error("expected a number"))?;
+                self.out.extend(n.to_le_bytes());
+            }
+            "string" => {
+                let s = v.as_str().ok_or_else(|| self.error("expected a string"))?;
+                self.bytes(s.as_bytes())?;
+            }
+            "bytes" => {
+                let bytes = v.as_str().and_then(|s| from_hex(s).ok()).ok_or_else(|| self.error("expected a hex string"))?;
+                self.bytes(&bytes)?;
+            }
+            "int128" | "int256" => {
+                let len = if name == "int128" { 16 } else { 32 };
+                let bytes = v.as_str().and_then(|s| from_hex(s).ok()).filter(|b| b.len() == len);
+                let bytes = bytes.ok_or_else(|| self.error(format!("expected a hex string of {len} bytes")))?;
+                self.out.extend(bytes);
+            }
+            "true" => {}
+            _ => {
+                let d = self.bare_definition(v, name)?;
+                self.fields(v, d)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn bare_definition(&self, v: &Value, name: &str) -> eyre::Result<Definition<'a>> {
+        let constructor = v.get(NAME_KEY).and_then(|n| n.as_str());
+        self.codec.bare_constructor(name, constructor).map_err(|e| self.error(e))
+    }
+
+    /// the definition named by `_`
+    fn named(&self, v: &Value) -> eyre::Result<(u32, Definition<'a>)> {
+        let name = v.get(NAME_KEY).and_then(|n| n.as_str()).ok_or_else(|| self.error(format!("expected an object with a `{NAME_KEY}` field")))?;
+        self.codec.names.get(name).copied().ok_or_else(|| self.error(format!("`{name}` isn't defined in this layer")))
+    }
+
+    fn boxed_any(&mut self, v: &Value) -> eyre::Result<()> {
+        if let Some(b) = v.as_bool() {
+            self.out.extend(if b { BOOL_TRUE_ID } else { BOOL_FALSE_ID }.to_le_bytes());
+            return Ok(());
+        }
+        let (id, d) = self.named(v)?;
+        self.out.extend(id.to_le_bytes());
+        self.fields(v, d)
+    }
+
+    fn fields(&mut self, v: &Value, d: Definition) -> eyre::Result<()> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(format!("objects are nested deeper than {MAX_DEPTH} levels")));
+        }
+        self.depth += 1;
+        let result = self.parameters(v, d);
+        self.depth -= 1;
+        result
+    }
+
+    fn parameters(&mut self, v: &Value, d: Definition) -> eyre::Result<()> {
+        let Value::Object(object) = v else {
+            return Err(self.error("expected an object"));
+        };
+        let mut flags = HashMap::<&str, u32>::new();
+        for p in d.parameters() {
+            if let TlTypeRef::Flag { field, bit, inner } = &p._type {
+                if is_present(object.get(p.name.as_str()), inner) {
+                    *flags.entry(field).or_default() |= 1 << bit;
+                }
+            }
+        }
+
+        for p in d.parameters() {
+            self.path.push(p.name.to_string());
+            let field = object.get(p.name.as_str());
+            match &p._type {
+                TlTypeRef::Nat => self.out.extend(flags.get(p.name.as_str()).copied().unwrap_or_default().to_le_bytes()),
+                TlTypeRef::Flag { inner, .. } => match field {
+                    Some(field) if is_present(Some(field), inner) => self.value(field, inner, d.type_params())?,
+                    _ => {}
+                },
+                t => {
+                    let field = field.ok_or_else(|| self.error("missing field"))?;
+                    self.value(field, t, d.type_params())?;
+                }
+            }
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    /// `string` and `bytes`, length prefixed and padded to 4 bytes
+    fn bytes(&mut self, bytes: &[u8]) -> eyre::Result<()> {
+        let header = match bytes.len() {
+            len @ 0..=253 => vec![len as u8],
+            len @ 254..=0xffffff => vec![254, len as u8, (len >> 8) as u8, (len >> 16) as u8],
+            len => return Err(self.error(format!("{len} bytes don't fit in a tl string"))),
+        };
+        self.out.extend(&header);
+        self.out.extend(bytes);
+        let padding = (4 - (header.len() + bytes.len()) % 4) % 4;
+        self.out.extend(std::iter::repeat_n(0, padding));
+        Ok(())
+    }
+}
+
+/// whether an optional field is written, `flags.N?true` fields are written when they are `true`
+fn is_present(field: Option<&Value>, inner: &TlTypeRef) -> bool {
+    match (field, inner) {
+        (None | Some(Value::Null), _) => false,
+        (Some(v), TlTypeRef::Bare { name }) if name == "true" => v.as_bool().unwrap_or(false),
+        _ => true,
+    }
+}
+
+/// the fewest bytes a value of `t` takes, bare constructors count as none
+fn min_size(t: &TlTypeRef) -> usize {
+    match t {
+        TlTypeRef::Bare { name } => match name.as_str() {
+            "int" | "string" | "bytes" => 4,
+            "long" | "double" => 8,
+            "int128" => 16,
+            "int256" => 32,
+            _ => 0,
+        },
+        TlTypeRef::Flag { .. } => 0,
+        _ => 4,
+    }
+}
+
+fn integer(v: &Value) -> Option<i64> {
+    match v {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_u64().map(|n| n as i64)),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+struct Reader<'c, 'a, 'b> {
+    codec: &'c TlCodec<'a>,
+    bytes: &'b [u8],
+    pos: usize,
+    path: Vec<String>,
+    /// objects around the current value
+    depth: usize,
+    /// keep what was read when a field fails instead of failing the whole value
+    lenient: bool,
+    /// the first error of a lenient read, nothing is read after it
+    failure: Option<String>,
+}
+
+impl Reader<'_, '_, '_> {
+    fn error<M: Display>(&self, message: M) -> eyre::Report {
+        match self.path.is_empty() {
+            true => eyre!("at byte {}: {message}", self.pos),
+            false => eyre!("at byte {}, `{}`: {message}", self.pos, self.path.join(".")),
+        }
+    }
+
+    fn take(&mut self, n: usize) -> eyre::Result<&[u8]> {
+        if self.bytes.len() - self.pos < n {
+            return Err(self.error(format!("expected {n} more bytes, only {} are left", self.bytes.len() - self.pos)));
+        }
+        self.pos += n;
+        Ok(&self.bytes[self.pos - n..self.pos])
+    }
+
+    fn u32(&mut self) -> eyre::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn value(&mut self, t: &TlTypeRef, type_params: &[TlIdent]) -> eyre::Result<Value> {
+        match t {
+            TlTypeRef::Flag { inner, .. } => self.value(inner, type_params),
+            TlTypeRef::Nat => Ok(Value::from(self.u32()?)),
+            TlTypeRef::Generic { .. } => self.boxed_any(),
+            TlTypeRef::Boxed { name } if type_params.contains(name) || name == "Object" => self.boxed_any(),
+            TlTypeRef::Boxed { name } if name == "Bool" => {
+                let start = self.pos;
+                match self.u32()? {
+                    BOOL_TRUE_ID => Ok(Value::Bool(true)),
+                    BOOL_FALSE_ID => Ok(Value::Bool(false)),
+                    id => {
+                        self.pos = start;
+                        Err(self.error(format!("expected a `Bool`, found #{}", constructor_id::format_id(id))))
+                    }
+                }
+            }
+            TlTypeRef::Boxed { name } => {
+                let start = self.pos;
+                let id = self.u32()?;
+                match self.codec.definition(id) {
+                    Some(d) if d.type_name() == Some(name.as_str()) => self.fields(d),
+                    Some(d) => {
+                        self.pos = start;
+                        Err(self.error(format!("expected a `{name}`, found `{}`", d.name())))
+                    }
+                    None => {
+                        self.pos = start;
+                        Err(self.error(format!("expected a `{name}`, found the unknown id #{}", constructor_id::format_id(id))))
+                    }
+                }
+            }
+            TlTypeRef::Vector { bare, inner } => {
+                if !bare {
+                    let start = self.pos;
+                    let id = self.u32()?;
+                    if id != VECTOR_ID {
+                        self.pos = start;
+                        return Err(self.error(format!("expected a `Vector`, found #{}", constructor_id::format_id(id))));
+                    }
+                }
+                let len = self.u32()? as usize;
+                //the length comes from the payload, it can't be more than the bytes left can hold.
+                //items that take no bytes are capped at the bytes left too
+                let left = self.bytes.len() - self.pos;
+                let fits = match min_size(inner) {
+                    0 => left,
+                    size => left / size,
+                };
+                if len > fits {
+                    return Err(self.error(format!("vector of {len} items doesn't fit in the {left} bytes left")));
+                }
+                let mut items = vec![];
+                for i in 0..len {
+                    if self.failure.is_some() {
+                        break;
+                    }
+                    self.path.push(i.to_string());
+                    items.push(self.value(inner, type_params)?);
+                    self.path.pop();
+                }
+                Ok(Value::Array(items))
+            }
+            TlTypeRef::Bare { name } => self.bare(name),
+        }
+    }
+
+    fn bare(&mut self, name: &str) -> eyre::Result<Value> {
+        Ok(match name {
+            "int" => Value::from(i32::from_le_bytes(self.take(4)?.try_into()?)),
+            "long" => Value::from(i64::from_le_bytes(self.take(8)?.try_into()?)),
+            "double" => Value::from(f64::from_le_bytes(self.take(8)?.try_into()?)),
+            "string" => Value::from(String::from_utf8_lossy(&self.bytes()?).into_owned()),
+            "bytes" => Value::from(to_hex(&self.bytes()?)),
+            "int128" => Value::from(to_hex(self.take(16)?)),
+            "int256" => Value::from(to_hex(self.take(32)?)),
+            "true" => Value::Bool(true),
+            _ => {
+                let d = self.codec.bare_constructor(name, None).map_err(|e| self.error(e))?;
+                self.fields(d)?
+            }
+        })
+    }
+
+    fn boxed_any(&mut self) -> eyre::Result<Value> {
+        let start = self.pos;
+        match self.u32()? {
+            BOOL_TRUE_ID => Ok(Value::Bool(true)),
+            BOOL_FALSE_ID => Ok(Value::Bool(false)),
+            VECTOR_ID => {
+                self.pos = start;
+                Err(self.error("found a `Vector`, its item type has to be given"))
+            }
+            id => match self.codec.definition(id) {
+                Some(d) => self.fields(d),
+                None => {
+                    self.pos = start;
+                    Err(self.error(format!("unknown id #{}", constructor_id::format_id(id))))
+                }
+            },
+        }
+    }
+
+    fn fields(&mut self, d: Definition) -> eyre::Result<Value> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(format!("objects are nested deeper than {MAX_DEPTH} levels")));
+        }
+        self.depth += 1;
+        let result = self.parameters(d);
+        self.depth -= 1;
+        result
+    }
+
+    fn parameters(&mut self, d: Definition) -> eyre::Result<Value> {
+        let mut object = Map::new();
+        object.insert(NAME_KEY.to_owned(), Value::from(d.name()));
+        let mut flags = HashMap::new();
+        for p in d.parameters() {
+            if self.failure.is_some() {
+                break;
+            }
+            self.path.push(p.name.to_string());
+            let value = self.field(p, d, &mut flags);
+            self.path.pop();
+            match value {
+                Ok(Some(value)) => {
+                    object.insert(p.name.to_string(), value);
+                }
+                Ok(None) => {}
+                Err(e) if self.lenient => self.failure = Some(e.to_string()),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    /// `None` for a flag parameter whose bit isn't set
+    fn field<'d>(&mut self, p: &'d TlParameter, d: Definition, flags: &mut HashMap<&'d str, u32>) -> eyre::Result<Option<Value>> {
+        Ok(match &p._type {
+            TlTypeRef::Nat => {
+                let n = self.u32()?;
+                flags.insert(&p.name, n);
+                Some(Value::from(n))
+            }
+            TlTypeRef::Flag { field, bit, inner } => {
+                let set = flags.get(field.as_str()).is_some_and(|f| f & (1 << bit) != 0);
+                match inner.as_ref() {
+                    TlTypeRef::Bare { name } if name == "true" => Some(Value::Bool(set)),
+                    _ if set => Some(self.value(inner, d.type_params())?),
+                    _ => None,
+                }
+            }
+            t => Some(self.value(t, d.type_params())?),
+        })
+    }
+
+    /// `string` and `bytes`, length prefixed and padded to 4 bytes
+    fn bytes(&mut self) -> eyre::Result<Vec<u8>> {
+        let (header, len) = match self.take(1)?[0] {
+            254 => {
+                let len = self.take(3)?;
+                (4, len[0] as usize | (len[1] as usize) << 8 | (len[2] as usize) << 16)
+            }
+            255 => return Err(self.error("invalid string length prefix 255")),
+            len => (1, len as usize),
+        };
+        let bytes = self.take(len)?.to_vec();
+        self.take((4 - (header + len) % 4) % 4)?;
+        Ok(bytes)
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// whitespace and a `0x` prefix are ignored, dumps can be pasted as they are
+pub fn from_hex(s: &str) -> eyre::Result<Vec<u8>> {
+    let digits = s.trim().trim_start_matches("0x").chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+    if digits.len() % 2 != 0 {
+        bail!("hex string has an odd number of digits");
+    }
+    digits.chunks(2)
+        .map(|pair| {
+            let byte = pair.iter().collect::<String>();
+            u8::from_str_radix(&byte, 16).map_err(|_| eyre!("`{byte}` isn't a hex byte"))
+        })
+        .collect()
+}
+
+/// hex, or base64 (standard or url safe) when it isn't valid hex
+pub fn parse_payload(s: &str) -> eyre::Result<Vec<u8>> {
+    from_hex(s).or_else(|_| {
+        let base64 = s.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c { '-' => '+', '_' => '/', c => c })
+            .collect::<String>();
+        BASE64.decode(base64).map_err(|e| eyre!("payload is neither hex nor base64: {e}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+    use crate::models::requests::SchemaFamily;
+    use crate::models::tl_layer::TlLayer;
+    use crate::tl;
+
+    const SCHEMA: &str = "
+---types---
+point#a1b2c3d4 x:int y:int = Point;
+node#11111111 next:Node = Node;
+leaf#22222222 = Node;
+message#33333333 flags:# id:long text:string silent:flags.0?true reply_to:flags.1?int points:Vector<Point> raw:bytes key:int128 ratio:double = Message;
+wrapped#55555555 point:%Point = Wrapped;
+---functions---
+send#44444444 message:Message = Bool;
+";
+
+    fn schema() -> TlSchema {
+        tl::parse_schema(TlLayer { family: SchemaFamily::Custom, layer_id: 1, layer: SCHEMA.to_owned(), release_date: Default::default() }).unwrap()
+    }
+
+    fn message() -> Value {
+        json!({
+            "_": "message",
+            "flags": 2,
+            "id": -5_000_000_000i64,
+            "text": "padded to 4 bytes",
+            "silent": false,
+            "reply_to": 7,
+            "points": [{"_": "point", "x": 1, "y": -1}, {"_": "point", "x": 2, "y": 3}],
+            "raw": "00ff10",
+            "key": "000102030405060708090a0b0c0d0e0f",
+            "ratio": 0.5,
+        })
+    }
+
+    fn nested(depth: usize) -> Value {
+        (0..depth).fold(json!({"_": "leaf"}), |next, _| json!({"_": "node", "next": next}))
+    }
+
+    #[test]
+    fn round_trips_objects_and_calls() {
+        let schema = schema();
+        let codec = TlCodec::new(&schema);
+        let call = json!({"_": "send", "message": message()});
+        for value in [message(), call, json!({"_": "wrapped", "point": {"x": 4, "y": 5}})] {
+            let bytes = codec.encode(&value, None).unwrap();
+            let mut expected = value.clone();
+            if expected["_"] == "wrapped" {
+                expected["point"]["_"] = json!("point");
+            }
+            assert_eq!(codec.decode(&bytes, None).unwrap(), expected);
+        }
+        let long_string = json!({"_": "message", "flags": 0, "id": 1, "text": "x".repeat(300), "silent": false, "points": [], "raw": "", "key": "00".repeat(16), "ratio": 1.0});
+        assert_eq!(codec.decode(&codec.encode(&long_string, None).unwrap(), None).unwrap(), long_string);
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let schema = schema();
+        let codec = TlCodec::new(&schema);
+        let bytes = codec.encode(&json!({"_": "send", "message": message()}), None).unwrap();
+        for len in 0..bytes.len() {
+            assert!(codec.decode(&bytes[..len], None).is_err(), "{len} of {} bytes were accepted", bytes.len());
+        }
+        assert!(codec.decode(&[bytes.as_slice(), &[0; 4]].concat(), None).unwrap_err().to_string().contains("4 extra bytes"));
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let schema = schema();
+        let codec = TlCodec::new(&schema);
+        assert!(codec.encode(&nested(MAX_DEPTH - 1), None).is_ok());
+        assert!(codec.encode(&nested(MAX_DEPTH), None).unwrap_err().to_string().contains("nested deeper"));
+
+        let mut bytes = 0x11111111u32.to_le_bytes().repeat(100_000);
+        bytes.extend(0x22222222u32.to_le_bytes());
+        assert!(codec.decode(&bytes, None).unwrap_err().to_string().contains("nested deeper"));
+    }
+
+    #[test]
+    fn rejects_vector_lengths_the_payload_cant_hold() {
+        let schema = schema();
+        let codec = TlCodec::new(&schema);
+        let points = TlTypeRef::Vector { bare: false, inner: Box::new(TlTypeRef::Boxed { name: "Point".into() }) };
+        let mut bytes = VECTOR_ID.to_le_bytes().to_vec();
+        bytes.extend(u32::MAX.to_le_bytes());
+        bytes.extend(codec.encode(&json!({"_": "point", "x": 1, "y": 2}), None).unwrap());
+        assert!(codec.decode(&bytes, Some(&points)).unwrap_err().to_string().contains("doesn't fit"));
+
+        let flags = TlTypeRef::Vector { bare: true, inner: Box::new(TlTypeRef::Bare { name: "true".into() }) };
+        assert!(codec.decode(&u32::MAX.to_le_bytes(), Some(&flags)).unwrap_err().to_string().contains("doesn't fit"));
+    }
+
+    #[test]
+    fn bare_values_name_their_only_constructor() {
+        let schema = schema();
+        let codec = TlCodec::new(&schema);
+        let wrapped = |point: Value| codec.encode(&json!({"_": "wrapped", "point": point}), None);
+        assert!(wrapped(json!({"_": "point", "x": 1, "y": 2})).is_ok());
+        let e = wrapped(json!({"_": "leaf", "x": 1, "y": 2})).unwrap_err().to_string();
+        assert!(e.contains("`%Point` is always `point`, found `leaf`"), "{e}");
+    }
+}
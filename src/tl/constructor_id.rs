@@ -0,0 +1,103 @@
+use std::fmt::Write;
+use itertools::Itertools;
+use crate::continue_if;
+use crate::tl::parser::TlDeclaration;
+use crate::tl::tl_type_ref::TlTypeRef;
+
+/// the text telegram's tooling hashes to get a constructor id:
+/// no `#id`, no `flags.N?true` parameters, `bytes` written as `string`, `<>` and `{}` removed
+pub fn normalized_definition(d: &TlDeclaration) -> String {
+    let mut out = d.name.to_string();
+    for t in &d.type_params {
+        let _ = write!(out, " {t}:Type");
+    }
+    let is_bytes = |t: &TlTypeRef| matches!(t, TlTypeRef::Bare { name } if name == "bytes");
+    for p in &d.parameters {
+        continue_if!(p._type.is_true_flag());
+        out.push(' ');
+        if !p.name.is_empty() {
+            let _ = write!(out, "{}:", p.name);
+        }
+        let _ = match &p._type {
+            t if is_bytes(t) => write!(out, "string"),
+            TlTypeRef::Flag { field, bit, inner } if is_bytes(inner) => write!(out, "{field}.{bit}?string"),
+            t => write!(out, "{t}"),
+        };
+    }
+    let _ = write!(out, " = {}", d.result_type);
+
+    let mut normalized = String::with_capacity(out.len());
+    for c in out.chars() {
+        match c {
+            '<' => normalized.push(' '),
+            '>' | '{' | '}' => {}
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+pub fn compute_id(d: &TlDeclaration) -> u32 {
+    crc32fast::hash(normalized_definition(d).as_bytes())
+}
+
+pub fn parse_id(id: &str) -> Option<u32> {
+    u32::from_str_radix(id, 16).ok()
+}
+
+/// ids a client log might mean by `s`: `0x38fe25b7`, `#38fe25b7`, `38fe25b7`, `-1174591225` or `3120376071`.
+/// plain digits are read both as hex and as a decimal
+pub fn lookup_ids(s: &str) -> Vec<u32> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).or_else(|| s.strip_prefix('#')) {
+        return parse_id(hex).into_iter().collect();
+    }
+    if s.starts_with('-') {
+        return s.parse::<i32>().map(|id| id as u32).into_iter().collect();
+    }
+    let hex = parse_id(s);
+    let decimal = s.parse::<u32>().ok();
+    hex.into_iter().chain(decimal).unique().collect()
+}
+
+pub fn format_id(id: u32) -> String {
+    format!("{id:x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tl::parser::{self, TlStatement};
+
+    /// `(declared, computed)` ids of every declaration in `src`
+    fn ids(src: &str) -> Vec<(u32, u32)> {
+        parser::parse(src).statements.iter().filter_map(|s| match s {
+            TlStatement::Declaration(d) => Some((parse_id(d.id.as_deref()?)?, compute_id(d))),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn computes_the_ids_of_api_tl() {
+        let src = "inputPeerEmpty#7f3b18ea = InputPeer;
+vector#1cb5c415 {t:Type} # [ t ] = Vector t;
+resPQ#05162463 nonce:int128 server_nonce:int128 pq:string server_public_key_fingerprints:Vector<long> = ResPQ;
+username#b4073647 flags:# editable:flags.0?true active:flags.1?true username:string = Username;
+photoStrippedSize#e0b0bc2e type:string bytes:bytes = PhotoSize;
+---functions---
+invokeWithLayer#da9b0d0d {X:Type} layer:int query:!X = X;
+users.getUsers#d91a548 id:Vector<InputUser> = Vector<User>;";
+        let ids = ids(src);
+        assert_eq!(ids.len(), 7);
+        for (declared, computed) in ids {
+            assert_eq!(format_id(computed), format_id(declared));
+        }
+    }
+
+    #[test]
+    fn normalizes_flags_bytes_and_brackets() {
+        let parsed = parser::parse("a#1 {X:Type} flags:# b:flags.0?true c:flags.1?bytes d:Vector<int> = A;");
+        let TlStatement::Declaration(d) = &parsed.statements[0] else { panic!() };
+        assert_eq!(normalized_definition(d), "a X:Type flags:# c:flags.1?string d:Vector int = A");
+    }
+}
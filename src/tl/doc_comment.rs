@@ -0,0 +1,84 @@
+use crate::tl::parser::TlDeclaration;
+
+/// a block of `//@key value` comments (tdlib style documentation).
+/// `//-` lines continue the previous value
+#[derive(Debug, Default)]
+pub struct TlDocComment {
+    fields: Vec<(String, String)>,
+    last_line: usize,
+}
+
+impl TlDocComment {
+    /// feeds one comment line, lines that aren't documentation are ignored
+    pub fn push(&mut self, text: &str, line: usize) {
+        let text = text.trim();
+        let (leading, fields) = split_fields(text);
+        if let Some(rest) = leading.strip_prefix('-') {
+            if line != self.last_line + 1 {
+                return;
+            }
+            let Some((_, value)) = self.fields.last_mut() else {
+                return;
+            };
+            value.push(' ');
+            value.push_str(rest.trim());
+        } else if !leading.is_empty() || fields.is_empty() {
+            return;
+        }
+        for part in fields {
+            let (key, value) = part.split_once(char::is_whitespace).unwrap_or((part, ""));
+            self.fields.push((key.to_owned(), value.trim().to_owned()));
+        }
+        self.last_line = line;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// whether the block ends right above `line`
+    pub fn is_attached_to(&self, line: usize) -> bool {
+        !self.is_empty() && line == self.last_line + 1
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.to_owned())
+    }
+
+    /// `//@class Name @description ...` documents a type instead of the next definition
+    pub fn class(&self) -> Option<String> {
+        self.get("class")
+    }
+
+    pub fn apply(&self, declaration: &mut TlDeclaration) {
+        declaration.description = self.get("description");
+        for param in &mut declaration.parameters {
+            param.description = self.get(&param.name);
+            //tdlib documents a parameter called `description` as `param_description`
+            if param.name == "description" {
+                param.description = self.get("param_description");
+            }
+        }
+    }
+}
+
+/// splits `text @a text @b text` on every `@` that starts a word, returns the text before the first field separately
+fn split_fields(text: &str) -> (&str, Vec<&str>) {
+    let mut starts = vec![];
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        if c == '@' && prev.is_whitespace() {
+            starts.push(i + 1);
+        }
+        prev = c;
+    }
+    let leading = starts.first().map(|first| &text[..first - 1]).unwrap_or(text).trim_end();
+    let fields = starts.iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let end = starts.get(n + 1).map(|next| next - 1).unwrap_or(text.len());
+            text[start..end].trim_end()
+        })
+        .collect();
+    (leading, fields)
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use crate::models::responses::{FunctionHistory, ObjectHistory};
+use crate::tl::layer_diff::TlChange;
+use crate::tl::tl_type_ref::TlTypeRef;
+
+/// what a change does to clients built against the older layer, ordered from harmless to dangerous
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TlImpact {
+    /// new definitions, new flag parameters and documentation, old clients keep working
+    Additive,
+    /// the bytes stay the same but generated code has to change: renames, removed optional parameters
+    SourceBreaking,
+    /// old clients can't read or write it anymore: new ids, reordered or retyped parameters
+    WireBreaking,
+}
+
+impl TlImpact {
+    pub fn is_breaking(&self) -> bool {
+        *self != TlImpact::Additive
+    }
+}
+
+/// a change and its [`TlImpact`]
+#[derive(Serialize, Debug)]
+pub struct Classified<T> {
+    #[serde(flatten)]
+    pub change: T,
+    pub impact: TlImpact,
+}
+
+pub trait Classify: Sized {
+    fn impact(&self) -> TlImpact;
+
+    fn classified(self) -> Classified<Self> {
+        Classified { impact: self.impact(), change: self }
+    }
+}
+
+/// a flag parameter is optional, everything else changes the layout
+pub fn param_added(param_type: &TlTypeRef) -> TlImpact {
+    match param_type {
+        TlTypeRef::Flag { .. } => TlImpact::Additive,
+        _ => TlImpact::WireBreaking,
+    }
+}
+
+pub fn param_deleted(param_type: &TlTypeRef) -> TlImpact {
+    match param_type {
+        TlTypeRef::Flag { .. } => TlImpact::SourceBreaking,
+        _ => TlImpact::WireBreaking,
+    }
+}
+
+/// removed constructors are only missing from generated code, calls to a removed function fail
+pub fn removed(is_function: bool) -> TlImpact {
+    if is_function { TlImpact::WireBreaking } else { TlImpact::SourceBreaking }
+}
+
+impl Classify for TlChange<'_> {
+    fn impact(&self) -> TlImpact {
+        match self {
+            TlChange::Renamed { .. } => TlImpact::SourceBreaking,
+            TlChange::IdChanged { .. } | TlChange::TypeChanged { .. } | TlChange::ReturnTypeChanged { .. } => TlImpact::WireBreaking,
+            TlChange::ParamAdded { param_type, .. } => param_added(param_type),
+            TlChange::ParamDeleted { param_type, .. } => param_deleted(param_type),
+            TlChange::ParamChanged { .. } | TlChange::ParamsReordered => TlImpact::WireBreaking,
+            TlChange::DescriptionChanged => TlImpact::Additive,
+        }
+    }
+}
+
+impl Classify for ObjectHistory<'_> {
+    fn impact(&self) -> TlImpact {
+        match self {
+            ObjectHistory::AddedIn { .. } => TlImpact::Additive,
+            ObjectHistory::DeletedIn { .. } => removed(false),
+            ObjectHistory::Renamed { .. } => TlImpact::SourceBreaking,
+            ObjectHistory::IdChanged { .. } | ObjectHistory::ParamChanged { .. } | ObjectHistory::ParamsReordered { .. } => TlImpact::WireBreaking,
+            ObjectHistory::ParamAdded { param_type, .. } => param_added(param_type),
+            ObjectHistory::ParamDeleted { param_type, .. } => param_deleted(param_type),
+        }
+    }
+}
+
+impl Classify for FunctionHistory<'_> {
+    fn impact(&self) -> TlImpact {
+        match self {
+            FunctionHistory::AddedIn { .. } => TlImpact::Additive,
+            FunctionHistory::DeletedIn { .. } => removed(true),
+            FunctionHistory::Renamed { .. } => TlImpact::SourceBreaking,
+            FunctionHistory::IdChanged { .. } | FunctionHistory::ParamChanged { .. } | FunctionHistory::ParamsReordered { .. } | FunctionHistory::ReturnTypeChanged { .. } => TlImpact::WireBreaking,
+            FunctionHistory::ParamAdded { param_type, .. } => param_added(param_type),
+            FunctionHistory::ParamDeleted { param_type, .. } => param_deleted(param_type),
+        }
+    }
+}
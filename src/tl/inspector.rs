@@ -0,0 +1,82 @@
+use std::cmp::Reverse;
+use eyre::bail;
+use serde::Serialize;
+use crate::models::requests::SchemaFamily;
+use crate::tl::codec::{TlCodec, TlInspection};
+use crate::tl::{constructor_id, TlSchema};
+
+/// what a captured payload decodes to under the layers that define its leading id
+#[derive(Serialize, Debug)]
+pub struct PayloadReport {
+    /// the leading constructor id
+    pub id: String,
+    pub length: usize,
+    /// clean decodes come first, then the ones that read the most
+    pub results: Vec<LayerDecode>,
+}
+
+/// layers of a family that decode the payload the same way
+#[derive(Serialize, Debug)]
+pub struct LayerDecode {
+    pub family: SchemaFamily,
+    pub layers: Vec<i32>,
+    /// the whole payload was read without errors
+    pub clean: bool,
+    #[serde(flatten)]
+    pub inspection: TlInspection,
+}
+
+/// decodes `bytes` under every schema, `schemas` should be the ones that define the leading id
+pub fn inspect<'a>(bytes: &[u8], schemas: impl IntoIterator<Item=&'a TlSchema>) -> eyre::Result<PayloadReport> {
+    let id = leading_id(bytes)?;
+    let mut results: Vec<LayerDecode> = vec![];
+    for schema in schemas {
+        let inspection = TlCodec::new(schema).inspect(bytes);
+        match results.iter_mut().find(|r| r.family == schema.family && r.inspection == inspection) {
+            Some(r) => r.layers.push(schema.layer_id),
+            None => results.push(LayerDecode { family: schema.family, layers: vec![schema.layer_id], clean: inspection.is_clean(), inspection }),
+        }
+    }
+    results.sort_by_key(|r| (!r.clean, Reverse(r.inspection.consumed)));
+    Ok(PayloadReport { id: constructor_id::format_id(id), length: bytes.len(), results })
+}
+
+pub fn leading_id(bytes: &[u8]) -> eyre::Result<u32> {
+    match bytes.get(..4) {
+        Some(id) => Ok(u32::from_le_bytes(id.try_into()?)),
+        None => bail!("a payload starts with a 4 byte constructor id, this one has {} bytes", bytes.len()),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tl_layer::TlLayer;
+    use crate::tl;
+    use crate::tl::codec::{MAX_DEPTH, VECTOR_ID};
+
+    fn schema() -> TlSchema {
+        tl::parse_schema(TlLayer { family: SchemaFamily::Api, layer_id: 185, layer: include_str!("../../tests/fixtures/api_185.tl").to_owned(), release_date: Default::default() }).unwrap()
+    }
+
+    fn error(bytes: &[u8]) -> String {
+        let schema = schema();
+        let report = inspect(bytes, [&schema]).unwrap();
+        let result = &report.results[0];
+        assert!(!result.clean);
+        result.inspection.error.clone().unwrap()
+    }
+
+    #[test]
+    fn stops_at_nested_calls() {
+        let bytes = [0xda9b0d0du32.to_le_bytes(), 185u32.to_le_bytes()].concat().repeat(100_000);
+        assert!(error(&bytes).contains(&format!("nested deeper than {MAX_DEPTH} levels")));
+    }
+
+    #[test]
+    fn stops_at_vector_lengths_the_payload_cant_hold() {
+        let bytes = [0x0d91a548u32, VECTOR_ID, u32::MAX, 0xf7c1b13f].map(u32::to_le_bytes).concat();
+        assert!(error(&bytes).contains("doesn't fit"));
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use itertools::{EitherOrBoth, Itertools};
+use serde::Serialize;
+use crate::models::requests::{SchemaFamily, SchemaSection};
+use crate::models::responses::Diff;
+use crate::tl::{impact, matcher, printer};
+use crate::tl::impact::{Classified, Classify, TlImpact};
+use crate::tl::tl_constructor::TlConstructor;
+use crate::tl::tl_function::TlFunction;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_shared::TlShared;
+use crate::tl::tl_type::TlType;
+use crate::tl::tl_type_ref::TlTypeRef;
+use crate::tl::TlSchema;
+
+/// everything that was added, removed or changed between two layers, sorted by name
+#[derive(Serialize, Debug)]
+pub struct TlLayerDiff<'a> {
+    pub family: SchemaFamily,
+    pub from: i32,
+    pub to: i32,
+    pub types: Vec<TlTypeDiff<'a>>,
+    pub constructors: Vec<TlDefinitionDiff<'a>>,
+    pub functions: Vec<TlDefinitionDiff<'a>>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TlDiffKind {
+    Added,
+    Removed,
+    Changed,
+    /// removed under one name and added under another, `name` is the new one
+    Renamed,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TlTypeDiff<'a> {
+    pub name: &'a TlIdent,
+    pub kind: TlDiffKind,
+    pub impact: TlImpact,
+    pub constructors_added: Vec<&'a TlIdent>,
+    pub constructors_removed: Vec<&'a TlIdent>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TlDefinitionDiff<'a> {
+    pub name: &'a TlIdent,
+    pub kind: TlDiffKind,
+    /// the worst impact of the changes
+    pub impact: TlImpact,
+    /// the definition in `from` as a `.tl` line
+    pub before: Option<String>,
+    /// the definition in `to` as a `.tl` line
+    pub after: Option<String>,
+    /// empty unless the definition is in both layers
+    pub changes: Vec<Classified<TlChange<'a>>>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum TlChange<'a> {
+    Renamed { before: &'a TlIdent, after: &'a TlIdent },
+    IdChanged { before: &'a TlIdent, after: &'a TlIdent },
+    /// the constructor now belongs to another type
+    TypeChanged { before: &'a TlIdent, after: &'a TlIdent },
+    ReturnTypeChanged { before: &'a TlTypeRef, after: &'a TlTypeRef },
+    ParamAdded { name: &'a TlIdent, param_type: &'a TlTypeRef },
+    ParamDeleted { name: &'a TlIdent, param_type: &'a TlTypeRef },
+    ParamChanged { name: &'a TlIdent, diff: Vec<Diff<'a>> },
+    /// parameters kept by both layers are in a different order
+    ParamsReordered,
+    /// documentation of the definition or its parameters
+    DescriptionChanged,
+}
+
+impl TlLayerDiff<'_> {
+    pub fn count(&self) -> usize {
+        self.types.len() + self.constructors.len() + self.functions.len()
+    }
+
+    /// only the breaking changes, and the definitions that have any
+    pub fn breaking(mut self) -> Self {
+        self.types.retain(|t| t.impact.is_breaking());
+        for d in self.constructors.iter_mut().chain(&mut self.functions) {
+            d.changes.retain(|c| c.impact.is_breaking());
+        }
+        self.constructors.retain(|d| d.impact.is_breaking());
+        self.functions.retain(|d| d.impact.is_breaking());
+        self
+    }
+}
+
+impl TlDefinitionDiff<'_> {
+    fn new<'a>(name: &'a TlIdent, kind: TlDiffKind, before: Option<String>, after: Option<String>, changes: Vec<TlChange<'a>>, is_function: bool) -> TlDefinitionDiff<'a> {
+        let changes = changes.into_iter().map(Classify::classified).collect::<Vec<_>>();
+        let impact = match kind {
+            TlDiffKind::Added => TlImpact::Additive,
+            TlDiffKind::Removed => impact::removed(is_function),
+            TlDiffKind::Changed | TlDiffKind::Renamed => changes.iter().map(|c| c.impact).max().unwrap_or(TlImpact::Additive),
+        };
+        TlDefinitionDiff { name, kind, impact, before, after, changes }
+    }
+}
+
+pub fn diff<'a>(from: &'a TlSchema, to: &'a TlSchema, section: SchemaSection) -> TlLayerDiff<'a> {
+    let renamed = matcher::renamed(&matcher::constructors(from, section), &matcher::constructors(to, section));
+    let constructors = diff_definitions(&constructors(from, section), &constructors(to, section), &renamed, false, |&(t, c)| printer::constructor_line(c, t), |&(ta, a), &(tb, b)| {
+        let mut changes = vec![];
+        if ta != tb {
+            changes.push(TlChange::TypeChanged { before: ta, after: tb });
+        }
+        definition_changes(&mut changes, (&a.id, &b.id), (&a.parameters, &b.parameters), (&a.description, &b.description));
+        changes
+    });
+    let renamed = matcher::renamed(&matcher::functions(from, section), &matcher::functions(to, section));
+    let functions = diff_definitions(&functions(from, section), &functions(to, section), &renamed, true, |f| printer::function_line(f), |a, b| {
+        let mut changes = vec![];
+        if a.return_type != b.return_type {
+            changes.push(TlChange::ReturnTypeChanged { before: &a.return_type, after: &b.return_type });
+        }
+        definition_changes(&mut changes, (&a.id, &b.id), (&a.parameters, &b.parameters), (&a.description, &b.description));
+        changes
+    });
+    TlLayerDiff { family: to.family, from: from.layer_id, to: to.layer_id, types: diff_types(from, to, section), constructors, functions }
+}
+
+fn constructors(schema: &TlSchema, section: SchemaSection) -> BTreeMap<&TlIdent, (&TlIdent, &TlShared<TlConstructor>)> {
+    schema.objects(section).flat_map(|t| t.constructors.iter().map(|c| (&c.name, (&t.name, c)))).collect()
+}
+
+fn functions(schema: &TlSchema, section: SchemaSection) -> BTreeMap<&TlIdent, &TlShared<TlFunction>> {
+    schema.functions(section).flat_map(|(_, f)| f).map(|f| (&f.name, f)).collect()
+}
+
+fn diff_types<'a>(from: &'a TlSchema, to: &'a TlSchema, section: SchemaSection) -> Vec<TlTypeDiff<'a>> {
+    let types = |s: &'a TlSchema| s.objects(section).map(|t| (&t.name, t)).collect::<BTreeMap<_, _>>();
+    let names = |t: &'a TlType| t.constructors.iter().map(|c| &c.name).collect::<Vec<_>>();
+    types(from).into_iter()
+        .merge_join_by(types(to), |(a, _), (b, _)| a.cmp(b))
+        .filter_map(|e| match e {
+            EitherOrBoth::Left((name, t)) => Some(TlTypeDiff { name, kind: TlDiffKind::Removed, impact: TlImpact::SourceBreaking, constructors_added: vec![], constructors_removed: names(t) }),
+            EitherOrBoth::Right((name, t)) => Some(TlTypeDiff { name, kind: TlDiffKind::Added, impact: TlImpact::Additive, constructors_added: names(t), constructors_removed: vec![] }),
+            EitherOrBoth::Both((name, a), (_, b)) => {
+                let (before, after) = (names(a), names(b));
+                let constructors_added = after.iter().filter(|c| !before.contains(c)).copied().collect::<Vec<_>>();
+                let constructors_removed = before.iter().filter(|c| !after.contains(c)).copied().collect::<Vec<_>>();
+                //code that matches on the constructors of a type breaks when one goes away
+                let impact = if constructors_removed.is_empty() { TlImpact::Additive } else { TlImpact::SourceBreaking };
+                (!constructors_added.is_empty() || !constructors_removed.is_empty())
+                    .then_some(TlTypeDiff { name, kind: TlDiffKind::Changed, impact, constructors_added, constructors_removed })
+            }
+        })
+        .collect()
+}
+
+/// definitions with the same name are compared, the ones `changes` finds nothing in are left out.
+/// a pair of `renamed` is compared like a single definition
+fn diff_definitions<'a, V>(before: &BTreeMap<&'a TlIdent, V>, after: &BTreeMap<&'a TlIdent, V>, renamed: &[(&'a TlIdent, &'a TlIdent)], is_function: bool, line: impl Fn(&V) -> String, changes: impl Fn(&V, &V) -> Vec<TlChange<'a>>) -> Vec<TlDefinitionDiff<'a>> {
+    before.iter()
+        .merge_join_by(after.iter(), |(a, _), (b, _)| a.cmp(b))
+        .filter_map(|e| match e {
+            EitherOrBoth::Left((name, _)) if renamed.iter().any(|(old, _)| old == name) => None,
+            EitherOrBoth::Left((name, d)) => Some(TlDefinitionDiff::new(name, TlDiffKind::Removed, Some(line(d)), None, vec![], is_function)),
+            EitherOrBoth::Right((name, d)) => match renamed.iter().find(|(_, new)| new == name) {
+                Some(&(old, _)) => {
+                    let a = &before[old];
+                    let mut changes = changes(a, d);
+                    changes.insert(0, TlChange::Renamed { before: old, after: name });
+                    Some(TlDefinitionDiff::new(name, TlDiffKind::Renamed, Some(line(a)), Some(line(d)), changes, is_function))
+                }
+                None => Some(TlDefinitionDiff::new(name, TlDiffKind::Added, None, Some(line(d)), vec![], is_function)),
+            },
+            EitherOrBoth::Both((name, a), (_, b)) => {
+                let changes = changes(a, b);
+                (!changes.is_empty()).then(|| TlDefinitionDiff::new(name, TlDiffKind::Changed, Some(line(a)), Some(line(b)), changes, is_function))
+            }
+        })
+        .collect()
+}
+
+/// whether the parameters both lists have are in a different order, the history of a definition checks the same
+pub fn reordered(before: &[TlParameter], after: &[TlParameter]) -> bool {
+    fn kept<'p>(params: &'p [TlParameter], other: &[TlParameter]) -> Vec<&'p TlIdent> {
+        params.iter().filter(|p| other.iter().any(|o| o.name == p.name)).map(|p| &p.name).collect()
+    }
+    kept(before, after) != kept(after, before)
+}
+
+/// parameters are matched by name, like the history of a single definition
+fn definition_changes<'a>(changes: &mut Vec<TlChange<'a>>, id: (&'a TlIdent, &'a TlIdent), params: (&'a [TlParameter], &'a [TlParameter]), description: (&'a Option<String>, &'a Option<String>)) {
+    let (before, after) = params;
+    if id.0 != id.1 {
+        changes.push(TlChange::IdChanged { before: id.0, after: id.1 });
+    }
+    for p in before.iter().filter(|p| !after.iter().any(|a| a.name == p.name)) {
+        changes.push(TlChange::ParamDeleted { name: &p.name, param_type: &p._type });
+    }
+    for p in after {
+        match before.iter().find(|b| b.name == p.name) {
+            Some(old) => if let Some(diff) = TlParameter::diff(p, old) {
+                changes.push(TlChange::ParamChanged { name: &p.name, diff });
+            },
+            None => changes.push(TlChange::ParamAdded { name: &p.name, param_type: &p._type }),
+        }
+    }
+
+    if reordered(before, after) {
+        changes.push(TlChange::ParamsReordered);
+    }
+
+    let documented = |p: &'a TlParameter| after.iter().find(|a| a.name == p.name).is_some_and(|a| a.description != p.description);
+    if description.0 != description.1 || before.iter().any(documented) {
+        changes.push(TlChange::DescriptionChanged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tl_layer::TlLayer;
+    use crate::tl;
+
+    fn parse(layer_id: i32, src: &str) -> TlSchema {
+        tl::parse_schema(TlLayer { family: SchemaFamily::Custom, layer_id, layer: src.to_owned(), release_date: Default::default() }).unwrap()
+    }
+
+    #[test]
+    fn reordered_parameters_break_the_wire() {
+        let before = parse(1, "point#1 x:int y:int = Point;\n---functions---\nmove#2 x:int y:int = Point;");
+        let after = parse(2, "point#1 y:int x:int = Point;\n---functions---\nmove#2 x:int z:int y:int = Point;");
+        let diff = diff(&before, &after, SchemaSection::All);
+        assert!(matches!(diff.constructors[0].changes[..], [Classified { change: TlChange::ParamsReordered, impact: TlImpact::WireBreaking }]));
+        assert!(diff.functions[0].changes.iter().all(|c| !matches!(c.change, TlChange::ParamsReordered)));
+    }
+
+    #[test]
+    fn only_kept_parameters_count_as_reordered() {
+        let layer = |src: &str| parse(1, &format!("point#1 {src} = Point;\n---functions---\n"));
+        let reordered = |a: &str, b: &str| reordered(&layer(a).api.objects[0].constructors[0].parameters, &layer(b).api.objects[0].constructors[0].parameters);
+        assert!(reordered("x:int y:int", "y:int x:int"));
+        assert!(!reordered("x:int y:int", "z:int x:int y:int"));
+        assert!(!reordered("x:int w:int y:int", "x:int y:int"));
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use crate::tl::lexer::{Lexer, TokenKind};
+
+/// what a schema says about itself in comments
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct TlLayerMetadata {
+    /// `// LAYER 185`
+    pub layer: Option<i32>,
+    /// `// version 1.8.0` or `// Version: 2`, in the order they appear
+    pub versions: Vec<String>,
+}
+
+impl TlLayerMetadata {
+    /// feeds the text of one comment, the last `LAYER` marker wins if there are several
+    pub fn push(&mut self, comment: &str) {
+        let text = comment.trim();
+        if let Some(layer) = strip_keyword(text, "layer").and_then(|n| n.parse().ok()) {
+            self.layer = Some(layer);
+        } else if let Some(version) = strip_keyword(text, "version").filter(|v| !v.is_empty()) {
+            self.versions.push(version.to_owned());
+        }
+    }
+}
+
+/// reads the `// LAYER N` marker and version comments without parsing the definitions
+pub fn read(src: &str) -> TlLayerMetadata {
+    let mut metadata = TlLayerMetadata::default();
+    for token in Lexer::tokenize(src) {
+        if let TokenKind::Comment(text) = token.kind {
+            metadata.push(text);
+        }
+    }
+    metadata
+}
+
+/// `LAYER 185` -> `185`, `Version: 1.2` -> `1.2`
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let prefix = text.get(..keyword.len())?;
+    let rest = &text[keyword.len()..];
+    if !prefix.eq_ignore_ascii_case(keyword) || !rest.starts_with([' ', '\t', ':']) {
+        return None;
+    }
+    Some(rest.trim_start_matches(':').trim())
+}
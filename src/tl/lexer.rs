@@ -0,0 +1,142 @@
+use crate::tl::tl_span::TlSpan;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind<'a> {
+    /// identifiers, numbers and hex constructor ids
+    Word(&'a str),
+    /// text after `//` up to the end of the line
+    Comment(&'a str),
+    /// `---types---` / `---functions---`, holds the inner name
+    Section(&'a str),
+    Hash,
+    Colon,
+    Semicolon,
+    Equals,
+    Question,
+    Bang,
+    Percent,
+    Dot,
+    Comma,
+    Star,
+    LAngle,
+    RAngle,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Unknown(char),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub span: TlSpan,
+}
+
+pub struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    line_start: usize,
+    /// column of `column_pos`, so columns are counted from the previous token instead of the line start
+    column_pos: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self { src, pos: 0, line: 1, line_start: 0, column_pos: 0, column: 1 }
+    }
+
+    pub fn tokenize(src: &'a str) -> Vec<Token<'a>> {
+        Lexer::new(src).collect()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.line_start = self.pos;
+        }
+        Some(c)
+    }
+
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+    }
+
+    fn span_from(&self, start: usize, line: usize, column: usize) -> TlSpan {
+        TlSpan { start, end: self.pos, line, column }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.eat_while(char::is_whitespace);
+
+        let start = self.pos;
+        let line = self.line;
+        if self.column_pos < self.line_start {
+            self.column_pos = self.line_start;
+            self.column = 1;
+        }
+        self.column += self.src[self.column_pos..start].chars().count();
+        self.column_pos = start;
+        let column = self.column;
+        let rest = &self.src[start..];
+        let c = self.bump()?;
+
+        let kind = match c {
+            '/' if rest.starts_with("//") => {
+                self.eat_while(|c| c != '\n');
+                TokenKind::Comment(&self.src[start + 2..self.pos])
+            }
+            '-' if rest.starts_with("---") => {
+                let inner = &rest[3..];
+                match inner.find("---").filter(|end| inner[..*end].chars().all(is_word_char)) {
+                    Some(end) => {
+                        self.pos = start + 3 + end + 3;
+                        TokenKind::Section(&inner[..end])
+                    }
+                    None => TokenKind::Unknown(c),
+                }
+            }
+            c if is_word_char(c) => {
+                self.eat_while(is_word_char);
+                TokenKind::Word(&self.src[start..self.pos])
+            }
+            '#' => TokenKind::Hash,
+            ':' => TokenKind::Colon,
+            ';' => TokenKind::Semicolon,
+            '=' => TokenKind::Equals,
+            '?' => TokenKind::Question,
+            '!' => TokenKind::Bang,
+            '%' => TokenKind::Percent,
+            '.' => TokenKind::Dot,
+            ',' => TokenKind::Comma,
+            '*' => TokenKind::Star,
+            '<' => TokenKind::LAngle,
+            '>' => TokenKind::RAngle,
+            '{' => TokenKind::LBrace,
+            '}' => TokenKind::RBrace,
+            '[' => TokenKind::LBracket,
+            ']' => TokenKind::RBracket,
+            c => TokenKind::Unknown(c),
+        };
+
+        Some(Token { kind, span: self.span_from(start, line, column) })
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::models::requests::SchemaSection;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_span::TlSpan;
+use crate::tl::tl_type_ref::TlTypeRef;
+use crate::tl::TlSchema;
+
+/// bare types the parser never sees a definition for
+const BUILTIN_BARE_TYPES: [&str; 8] = ["int", "long", "double", "string", "bytes", "int128", "int256", "true"];
+/// boxed types that are either builtin or defined by an ignored definition
+const BUILTIN_BOXED_TYPES: [&str; 12] = ["Bool", "True", "Error", "Null", "Int", "Long", "Double", "String", "Bytes", "Int128", "Int256", "Object"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum LintCheck {
+    /// a type, `%Type` or `!X` that nothing defines, for a boxed type that means no constructor produces it
+    UndefinedType,
+    /// a constructor name written where a boxed type belongs
+    ConstructorAsType,
+    /// two definitions declare the same id
+    DuplicateId,
+    /// `field.N?T` where `field` isn't a `#` parameter declared before it
+    MissingFlagField,
+}
+
+impl LintCheck {
+    pub const ALL: [LintCheck; 4] = [LintCheck::UndefinedType, LintCheck::ConstructorAsType, LintCheck::DuplicateId, LintCheck::MissingFlagField];
+}
+
+impl Display for LintCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            LintCheck::UndefinedType => "undefined_type",
+            LintCheck::ConstructorAsType => "constructor_as_type",
+            LintCheck::DuplicateId => "duplicate_id",
+            LintCheck::MissingFlagField => "missing_flag_field",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for LintCheck {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LintCheck::ALL
+            .into_iter()
+            .find(|c| c.to_string() == s.trim())
+            .ok_or_else(|| format!("unknown lint check `{s}`, expected one of {}", LintCheck::ALL.map(|c| c.to_string()).join(", ")))
+    }
+}
+
+/// which checks to run
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    pub checks: Vec<LintCheck>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self { checks: LintCheck::ALL.to_vec() }
+    }
+}
+
+impl LintConfig {
+    /// comma separated check names, `undefined_type,duplicate_id`
+    pub fn from_names(names: &str) -> Result<Self, String> {
+        let checks = names
+            .split(',')
+            .filter(|n| !n.trim().is_empty())
+            .map(LintCheck::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { checks })
+    }
+
+    fn enabled(&self, check: LintCheck) -> bool {
+        self.checks.contains(&check)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct LintFinding {
+    pub check: LintCheck,
+    /// name of the definition the finding belongs to
+    pub definition: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for LintFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{} [{}] {}: {}", self.line, self.column, self.check, self.definition, self.message)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct LintReport {
+    pub layer_id: i32,
+    pub findings: BTreeMap<LintCheck, Vec<LintFinding>>,
+}
+
+impl LintReport {
+    pub fn count(&self) -> usize {
+        self.findings.values().map(|f| f.len()).sum()
+    }
+}
+
+/// runs the enabled checks over the definitions of `section`, names are resolved against the whole layer
+pub fn lint(schema: &TlSchema, section: SchemaSection, config: &LintConfig) -> LintReport {
+    let mut linter = Linter {
+        config,
+        type_names: schema.objects(SchemaSection::All).map(|t| t.name.as_str()).collect(),
+        constructor_names: schema.objects(SchemaSection::All).flat_map(|t| &t.constructors).map(|c| c.name.as_str()).collect(),
+        findings: BTreeMap::new(),
+    };
+
+    let mut ids = HashMap::<&str, Vec<(&str, TlSpan)>>::new();
+    for tl_type in schema.objects(section) {
+        for ctor in &tl_type.constructors {
+            linter.parameters(&ctor.name, &ctor.type_params, &ctor.parameters, ctor.span);
+            ids.entry(&ctor.id).or_default().push((&ctor.name, ctor.span));
+        }
+    }
+    for function in schema.functions(section).flat_map(|(_, f)| f) {
+        linter.parameters(&function.name, &function.type_params, &function.parameters, function.span);
+        linter.type_ref(&function.name, &function.type_params, &function.return_type, function.span);
+        ids.entry(&function.id).or_default().push((&function.name, function.span));
+    }
+
+    if config.enabled(LintCheck::DuplicateId) {
+        for (id, mut definitions) in ids.into_iter().filter(|(id, d)| !id.is_empty() && d.len() > 1) {
+            definitions.sort_by_key(|(_, span)| span.start);
+            let names = definitions.iter().map(|(n, _)| *n).collect::<Vec<_>>().join(", ");
+            for (name, span) in &definitions {
+                linter.push(LintCheck::DuplicateId, name, *span, format!("id #{id} is shared by {names}"));
+            }
+        }
+    }
+
+    for findings in linter.findings.values_mut() {
+        findings.sort_by_key(|f| (f.line, f.column));
+    }
+    LintReport { layer_id: schema.layer_id, findings: linter.findings }
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    type_names: HashSet<&'a str>,
+    constructor_names: HashSet<&'a str>,
+    findings: BTreeMap<LintCheck, Vec<LintFinding>>,
+}
+
+impl Linter<'_> {
+    fn push(&mut self, check: LintCheck, definition: &str, span: TlSpan, message: String) {
+        if !self.config.enabled(check) {
+            return;
+        }
+        self.findings.entry(check).or_default().push(LintFinding {
+            check,
+            definition: definition.to_owned(),
+            line: span.line,
+            column: span.column,
+            message,
+        });
+    }
+
+    /// `span` is the definition's, parameter spans are relative to it
+    fn parameters(&mut self, definition: &str, type_params: &[TlIdent], parameters: &[TlParameter], span: TlSpan) {
+        for (i, param) in parameters.iter().enumerate() {
+            let param_span = param.span.within(span);
+            if let Some((field, bit)) = param._type.flag() {
+                let declared = parameters[..i].iter().any(|p| p.name == field && p._type == TlTypeRef::Nat);
+                if !declared {
+                    self.push(LintCheck::MissingFlagField, definition, param_span, format!("`{}` depends on bit {bit} of `{field}` which isn't a `#` parameter declared before it", param.name));
+                }
+            }
+            self.type_ref(definition, type_params, &param._type, param_span);
+        }
+    }
+
+    fn type_ref(&mut self, definition: &str, type_params: &[TlIdent], type_ref: &TlTypeRef, span: TlSpan) {
+        match type_ref {
+            TlTypeRef::Flag { inner, .. } | TlTypeRef::Vector { inner, .. } => self.type_ref(definition, type_params, inner, span),
+            TlTypeRef::Nat => {}
+            TlTypeRef::Generic { name } => {
+                if !type_params.contains(name) {
+                    self.push(LintCheck::UndefinedType, definition, span, format!("`!{name}` doesn't refer to a `{{{name}:Type}}` parameter"));
+                }
+            }
+            TlTypeRef::Boxed { name } => {
+                let defined = type_params.contains(name) || self.type_names.contains(name.as_str()) || BUILTIN_BOXED_TYPES.contains(&name.as_str());
+                if defined {
+                    return;
+                }
+                if self.constructor_names.contains(name.as_str()) {
+                    self.push(LintCheck::ConstructorAsType, definition, span, format!("`{name}` is a constructor, not a type"));
+                } else {
+                    self.push(LintCheck::UndefinedType, definition, span, format!("no constructor produces `{name}`"));
+                }
+            }
+            TlTypeRef::Bare { name } => {
+                let defined = self.type_names.contains(name.as_str())
+                    || self.constructor_names.contains(name.as_str())
+                    || BUILTIN_BARE_TYPES.contains(&name.as_str())
+                    || BUILTIN_BOXED_TYPES.contains(&name.as_str());
+                if !defined {
+                    self.push(LintCheck::UndefinedType, definition, span, format!("`{type_ref}` isn't defined"));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::requests::SchemaFamily;
+    use crate::models::tl_layer::TlLayer;
+    use crate::tl;
+
+    fn findings(src: &str) -> Vec<(LintCheck, String)> {
+        let schema = tl::parse_schema(TlLayer { family: SchemaFamily::Api, layer_id: 1, layer: src.to_owned(), release_date: Default::default() }).unwrap();
+        lint(&schema, SchemaSection::All, &LintConfig::default()).findings.into_values().flatten().map(|f| (f.check, f.message)).collect()
+    }
+
+    #[test]
+    fn reports_a_misspelled_boxed_type_as_undefined() {
+        let findings = findings("user#1 id:long = User;\n---functions---\ngetUser#2 id:long = Usr;");
+        assert_eq!(findings, vec![(LintCheck::UndefinedType, "no constructor produces `Usr`".to_owned())]);
+    }
+
+    #[test]
+    fn reports_types_only_parameters_and_results_use() {
+        let findings = findings("user#1 id:long photo:Photo = User;\n---functions---\ngetPhoto#2 id:long = Photo;");
+        assert_eq!(findings, vec![
+            (LintCheck::UndefinedType, "no constructor produces `Photo`".to_owned()),
+            (LintCheck::UndefinedType, "no constructor produces `Photo`".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn reports_a_boxed_constructor_name() {
+        let findings = findings("Wrapped#1 id:long = Wrapper;\n---functions---\ngetWrapped#2 = Wrapped;");
+        assert_eq!(findings, vec![(LintCheck::ConstructorAsType, "`Wrapped` is a constructor, not a type".to_owned())]);
+    }
+
+    #[test]
+    fn reports_flags_without_a_field_and_duplicate_ids() {
+        let findings = findings("a#1 x:flags.0?int = A;\nb#1 = A;\n---functions---\nc#2 = A;");
+        assert_eq!(findings.iter().map(|(c, _)| *c).collect::<Vec<_>>(), vec![LintCheck::DuplicateId, LintCheck::DuplicateId, LintCheck::MissingFlagField]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+use crate::models::requests::SchemaSection;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_parameter::TlParameter;
+use crate::tl::TlSchema;
+
+/// parameter-set similarity two definitions need to be taken for the same one
+const MIN_SIMILARITY: f64 = 0.75;
+
+/// what the matcher knows about a constructor or a function of one layer
+pub struct TlCandidate<'a> {
+    pub name: &'a TlIdent,
+    pub id: &'a TlIdent,
+    pub parameters: &'a [TlParameter],
+    /// type of a constructor, return type of a function
+    pub result: TlIdent,
+}
+
+pub fn constructors(schema: &TlSchema, section: SchemaSection) -> Vec<TlCandidate<'_>> {
+    schema.objects(section)
+        .flat_map(|t| t.constructors.iter().map(|c| TlCandidate { name: &c.name, id: &c.id, parameters: &c.parameters, result: t.name.clone() }))
+        .collect()
+}
+
+pub fn functions(schema: &TlSchema, section: SchemaSection) -> Vec<TlCandidate<'_>> {
+    schema.functions(section)
+        .flat_map(|(_, f)| f)
+        .map(|f| TlCandidate { name: &f.name, id: &f.id, parameters: &f.parameters, result: f.return_type.ident() })
+        .collect()
+}
+
+/// `(old, new)` names of the definitions of `before` that live on under another name in `after`.
+/// only names missing from the other layer are paired, a shared id always wins,
+/// otherwise they need the same type and similar parameters. ties are left unpaired
+pub fn renamed<'a>(before: &[TlCandidate<'a>], after: &[TlCandidate<'a>]) -> Vec<(&'a TlIdent, &'a TlIdent)> {
+    let names = |c: &[TlCandidate<'a>]| c.iter().map(|c| c.name).collect::<HashSet<_>>();
+    let (before_names, after_names) = (names(before), names(after));
+    let removed = before.iter().filter(|c| !after_names.contains(c.name)).collect::<Vec<_>>();
+    let added = after.iter().filter(|c| !before_names.contains(c.name)).collect::<Vec<_>>();
+
+    let mut scores = vec![];
+    for (i, old) in removed.iter().enumerate() {
+        for (j, new) in added.iter().enumerate() {
+            if let Some(score) = score(old, new) {
+                scores.push((score, i, j));
+            }
+        }
+    }
+    scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let (mut used_old, mut used_new) = (HashSet::new(), HashSet::new());
+    let mut pairs = vec![];
+    for &(score, i, j) in &scores {
+        if used_old.contains(&i) || used_new.contains(&j) {
+            continue;
+        }
+        let tied = scores.iter().any(|&(s, i2, j2)| s == score && (i2 == i) != (j2 == j) && !used_old.contains(&i2) && !used_new.contains(&j2));
+        if tied {
+            continue;
+        }
+        used_old.insert(i);
+        used_new.insert(j);
+        pairs.push((removed[i].name, added[j].name));
+    }
+    pairs
+}
+
+/// how alike two definitions are, `None` if they can't be the same one
+fn score(old: &TlCandidate, new: &TlCandidate) -> Option<f64> {
+    if !old.id.is_empty() && old.id == new.id {
+        return Some(2.0);
+    }
+    if old.result != new.result {
+        return None;
+    }
+    let similarity = similarity(old.parameters, new.parameters);
+    (similarity >= MIN_SIMILARITY).then_some(similarity)
+}
+
+/// jaccard index of the `name:type` pairs. definitions without parameters have nothing in common,
+/// only a shared id pairs them
+fn similarity(a: &[TlParameter], b: &[TlParameter]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    let common = a.iter().filter(|p| b.iter().any(|o| o.name == p.name && o._type == p._type)).count();
+    common as f64 / (a.len() + b.len() - common) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::requests::SchemaFamily;
+    use crate::models::tl_layer::TlLayer;
+    use crate::tl;
+
+    fn renamed_constructors(before: &str, after: &str) -> Vec<(String, String)> {
+        let parse = |layer_id, src: &str| tl::parse_schema(TlLayer { family: SchemaFamily::Custom, layer_id, layer: format!("{src}\n---functions---\n"), release_date: Default::default() }).unwrap();
+        let (before, after) = (parse(1, before), parse(2, after));
+        renamed(&constructors(&before, SchemaSection::All), &constructors(&after, SchemaSection::All))
+            .into_iter()
+            .map(|(old, new)| (old.to_string(), new.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn pairs_a_renamed_constructor() {
+        let pairs = renamed_constructors(
+            "chatPhoto#1c6e1c11 flags:# photo_id:long dc_id:int = ChatPhoto;",
+            "chatProfilePhoto#2c6e1c11 flags:# photo_id:long dc_id:int = ChatPhoto;",
+        );
+        assert_eq!(pairs, [("chatPhoto".to_owned(), "chatProfilePhoto".to_owned())]);
+    }
+
+    #[test]
+    fn keeps_unrelated_constructors_without_parameters_apart() {
+        let pairs = renamed_constructors("inputPeerEmpty#7f3b18ea = InputPeer;", "inputPeerSelf#7da07ec9 = InputPeer;");
+        assert!(pairs.is_empty(), "{pairs:?}");
+        let pairs = renamed_constructors("inputPeerEmpty#7f3b18ea = InputPeer;", "inputPeerNone#7f3b18ea = InputPeer;");
+        assert_eq!(pairs, [("inputPeerEmpty".to_owned(), "inputPeerNone".to_owned())]);
+    }
+}
@@ -1,74 +1,214 @@
 use std::collections::HashMap;
-use std::str::Lines;
+use std::fmt::Display;
 use chrono::NaiveDateTime;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use crate::continue_if;
+use crate::models::requests::{SchemaFamily, SchemaSection};
 use crate::models::tl_layer::TlLayer;
+use crate::tl::doc_comment::TlDocComment;
+use crate::tl::layer_metadata::TlLayerMetadata;
+use crate::tl::parser::{TlDeclaration, TlStatement};
 use crate::tl::tl_constructor::TlConstructor;
+use crate::tl::tl_diagnostic::TlDiagnostic;
 use crate::tl::tl_function::TlFunction;
-use crate::tl::tl_parameter::{parse_parameter};
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_shared::TlShared;
+use crate::tl::tl_span::TlSpan;
 use crate::tl::tl_type::TlType;
 
 pub mod tl_parameter;
 pub mod tl_constructor;
 pub mod tl_type;
 pub mod tl_function;
+pub mod tl_type_ref;
+pub mod tl_flag;
+pub mod tl_ident;
+pub mod tl_shared;
+pub mod tl_span;
+pub mod tl_diagnostic;
+pub mod tl_graph;
+pub mod lexer;
+pub mod parser;
+pub mod doc_comment;
+pub mod constructor_id;
+pub mod changelog;
+pub mod codec;
+pub mod inspector;
+pub mod impact;
+pub mod layer_metadata;
+pub mod layer_diff;
+pub mod printer;
+pub mod lint;
+pub mod matcher;
 pub mod schema_manager;
-const IGNORED_DEFINITIONS: [&str; 6] = ["boolFalse", "boolTrue", "true", "error", "vector", "null"];
+const IGNORED_DEFINITIONS: [&str; 7] = ["boolFalse", "boolTrue", "true", "vector", "null", "int128", "int256"];
+/// tdlib's `error` is a regular object that functions return
+const TELEGRAM_IGNORED_DEFINITIONS: [&str; 1] = ["error"];
 
-#[derive(Serialize, Deserialize, Debug)]
+/// flavour of the `.tl` file a schema was parsed from
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TlDialect {
+    /// telegram's `api.tl`, definitions carry `#id` and the mtproto part ends with `///////// Main application API`
+    #[default]
+    Telegram,
+    /// tdlib's `td_api.tl`, no ids and `//@` documentation on everything
+    Tdlib,
+}
+
+impl Display for TlDialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            TlDialect::Telegram => "telegram",
+            TlDialect::Tdlib => "tdlib",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Serialize, Debug)]
 pub struct TlSchema {
+    pub family: SchemaFamily,
     pub layer_id: i32,
+    pub dialect: TlDialect,
     pub release_date: NaiveDateTime,
+    /// the main application api
+    pub api: TlSection,
+    /// protocol level definitions, everything before `///////// Main application API`
+    pub mtproto: TlSection,
+    /// `// LAYER N` and version comments of the source
+    pub metadata: TlLayerMetadata,
+    /// recoverable problems found while parsing
+    pub diagnostics: Vec<TlDiagnostic>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct TlSection {
     pub objects: Vec<TlType>,
-    pub functions: HashMap<String, Vec<TlFunction>>,
+    pub functions: HashMap<TlIdent, Vec<TlShared<TlFunction>>>,
+    /// builtins like `int ? = Int;` and the definitions clients handle themselves (`boolTrue`, `vector`...),
+    /// kept as canonical lines for the printer
+    pub builtins: Vec<String>,
 }
-pub fn parse_schema(layer: TlLayer) -> TlSchema {
-    let definitions = layer.layer
-        .lines()
-        .filter(|l|
-        !l.is_empty() &&
-            !IGNORED_DEFINITIONS.iter().any(|ig| l.starts_with(ig)))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    //[0] is mtproto definition and [1] is the api
-    let sp = definitions.split("///////// Main application API").collect::<Vec<_>>();
-    let definitions = (if sp.len() == 2 { sp[1].to_owned() } else { definitions })
-        .lines()
-        .filter(|l| !l.starts_with("//"))
-        .collect::<Vec<_>>()
-        .join("\n")
-        .replace("---types---", "");
-
-    let spl = definitions.split("---functions---").collect::<Vec<_>>();
-    let objects = spl[0].lines();
-    let functions = spl[1].lines();
-    let objects = parse_objects(objects);
-    let functions = parse_functions(functions);
-
-    TlSchema { layer_id: layer.layer_id, objects, functions, release_date: layer.release_date }
+
+impl TlSchema {
+    pub fn sections(&self, section: SchemaSection) -> Vec<&TlSection> {
+        match section {
+            SchemaSection::Api => vec![&self.api],
+            SchemaSection::Mtproto => vec![&self.mtproto],
+            SchemaSection::All => vec![&self.api, &self.mtproto],
+        }
+    }
+
+    pub fn objects(&self, section: SchemaSection) -> impl Iterator<Item=&TlType> {
+        self.sections(section).into_iter().flat_map(|s| s.objects.iter())
+    }
+
+    /// functions grouped by namespace
+    pub fn functions(&self, section: SchemaSection) -> impl Iterator<Item=(&TlIdent, &Vec<TlShared<TlFunction>>)> {
+        self.sections(section).into_iter().flat_map(|s| s.functions.iter())
+    }
+
+    pub fn constructor(&self, name: &str, section: SchemaSection) -> Option<&TlShared<TlConstructor>> {
+        self.objects(section).flat_map(|t| &t.constructors).find(|c| c.name == name)
+    }
+
+    pub fn function(&self, name: &str, section: SchemaSection) -> Option<&TlShared<TlFunction>> {
+        self.functions(section).flat_map(|(_, f)| f).find(|f| f.name == name)
+    }
+}
+/// parses a layer, broken definitions are skipped and reported as warnings in [`TlSchema::diagnostics`].
+/// fails only when nothing usable is left
+pub fn parse_schema(layer: TlLayer) -> Result<TlSchema, Vec<TlDiagnostic>> {
+    let src = layer.layer.as_str();
+    let parsed = parser::parse(src);
+    let mut diagnostics = parsed.errors
+        .into_iter()
+        .map(|e| TlDiagnostic::warning(src, e.span, e.message))
+        .collect::<Vec<_>>();
+
+    let mut objects = vec![];
+    let mut functions = vec![];
+    let mut builtins = vec![];
+    let mut mtproto = None;
+    let mut declared_ids = 0;
+    let mut metadata = TlLayerMetadata::default();
+    let mut doc = TlDocComment::default();
+    let mut class_docs = HashMap::new();
+    for statement in parsed.statements {
+        match statement {
+            //everything before the marker is mtproto definition
+            TlStatement::ApiStart => {
+                mtproto = Some((std::mem::take(&mut objects), std::mem::take(&mut functions), std::mem::take(&mut builtins)));
+                doc = TlDocComment::default();
+            }
+            TlStatement::Comment { text, span } => {
+                metadata.push(text);
+                if !doc.is_attached_to(span.line) {
+                    take_class_doc(&mut doc, &mut class_docs);
+                }
+                doc.push(text, span.line);
+            }
+            TlStatement::Declaration(mut d) => {
+                if doc.class().is_none() && doc.is_attached_to(d.span.line) {
+                    doc.apply(&mut d);
+                }
+                take_class_doc(&mut doc, &mut class_docs);
+                if d.id.is_some() { declared_ids += 1; }
+                match d {
+                    d if d.is_builtin => builtins.push(d),
+                    d if d.is_function => functions.push(d),
+                    d => objects.push(d),
+                }
+            }
+        }
+    }
+    take_class_doc(&mut doc, &mut class_docs);
+
+    //tdlib never declares ids, telegram schemas always do
+    let dialect = if mtproto.is_none() && declared_ids == 0 { TlDialect::Tdlib } else { TlDialect::Telegram };
+    let (mtproto_objects, mtproto_functions, mut mtproto_builtins) = mtproto.unwrap_or_default();
+    //the dialect decides what is ignored, so ids are only checked now
+    let mtproto_objects = take_ignored(src, dialect, mtproto_objects, &mut mtproto_builtins, &mut diagnostics);
+    let mtproto_functions = take_ignored(src, dialect, mtproto_functions, &mut mtproto_builtins, &mut diagnostics);
+    let objects = take_ignored(src, dialect, objects, &mut builtins, &mut diagnostics);
+    let functions = take_ignored(src, dialect, functions, &mut builtins, &mut diagnostics);
+    if let Some(declared) = metadata.layer.filter(|l| *l != layer.layer_id) {
+        diagnostics.push(TlDiagnostic::warning(src, TlSpan::default(), format!("layer is stored as {} but the schema says `LAYER {declared}`", layer.layer_id)));
+    }
+
+    if objects.is_empty() && functions.is_empty() && mtproto_objects.is_empty() {
+        diagnostics.push(TlDiagnostic::error(src, TlSpan::default(), "layer doesn't contain any definition"));
+        return Err(diagnostics);
+    }
+    //secret chat schemas only define objects
+    if functions.is_empty() && layer.family != SchemaFamily::Secret {
+        diagnostics.push(TlDiagnostic::warning(src, TlSpan::default(), "layer doesn't contain any function, `---functions---` is probably missing"));
+    }
+
+    let api = TlSection { objects: parse_objects(layer.layer_id, objects, &class_docs), functions: parse_functions(layer.layer_id, functions), builtins: builtin_lines(builtins) };
+    let mtproto = TlSection { objects: parse_objects(layer.layer_id, mtproto_objects, &class_docs), functions: parse_functions(layer.layer_id, mtproto_functions), builtins: builtin_lines(mtproto_builtins) };
+
+    Ok(TlSchema { family: layer.family, layer_id: layer.layer_id, dialect, api, mtproto, release_date: layer.release_date, metadata, diagnostics })
 }
 
-fn parse_functions(functions: Lines) -> HashMap<String, Vec<TlFunction>> {
+fn parse_functions(layer_id: i32, functions: Vec<TlDeclaration>) -> HashMap<TlIdent, Vec<TlShared<TlFunction>>> {
+    let functions = tl_shared::share(layer_id, functions, |function| TlFunction {
+        id: function.id.unwrap_or_default(),
+        computed_id: function.computed_id,
+        name: function.name,
+        type_params: function.type_params,
+        flag_fields: tl_flag::flag_fields(&function.parameters),
+        parameters: function.parameters,
+        return_type: function.result_type,
+        description: function.description,
+    });
     let mut map = HashMap::new();
-    for function in functions {
-        continue_if!(function.is_empty());
-        let mut spl = function.split("=");
-        let definition = spl.next().unwrap();
-        let return_type = spl.next().unwrap().trim().replace(";", "");
-
-        let inner_return_type = if return_type.contains('<') {
-            Some(return_type.split("<").last().unwrap().split(">").next().unwrap().to_owned())
-        } else { None };
-
-        let mut spl = definition.split("#");
-
-        let name = spl.next().unwrap().trim().to_string();
-        let name_spl = name.split(".").collect::<Vec<_>>();
-        let k = if name_spl.len() == 2 { name_spl[0].to_string() } else { name.to_owned() };
-        let (id, parameters) = parse_parameter(&spl.collect::<Vec<_>>().join("#"));
-        let f = TlFunction { id, name, parameters, return_type, inner_return_type };
+    for f in functions {
+        let k = match f.name.split_once('.') {
+            Some((ns, _)) => TlIdent::new(ns),
+            None => f.name.to_owned(),
+        };
         map.entry(k).or_insert(vec![]).push(f);
     }
     let mut singles = vec![];
@@ -80,37 +220,101 @@ fn parse_functions(functions: Lines) -> HashMap<String, Vec<TlFunction>> {
     let mut map = map.into_iter()
         .filter(|a| !a.1.is_empty())
         .collect::<HashMap<_, _>>();
-    map.entry("Others".to_owned()).or_insert(singles);
+    map.entry(TlIdent::new("Others")).or_insert(singles);
     map
 }
 
-fn parse_objects(objects: Lines) -> Vec<TlType> {
-    let mut map = HashMap::new();
+/// moves the definitions clients handle themselves to `builtins` and verifies the ids of the rest
+fn take_ignored(src: &str, dialect: TlDialect, declarations: Vec<TlDeclaration>, builtins: &mut Vec<TlDeclaration>, diagnostics: &mut Vec<TlDiagnostic>) -> Vec<TlDeclaration> {
+    let mut kept = vec![];
+    for mut d in declarations {
+        let name = d.name.as_str();
+        if IGNORED_DEFINITIONS.contains(&name) || (dialect == TlDialect::Telegram && TELEGRAM_IGNORED_DEFINITIONS.contains(&name)) {
+            builtins.push(d);
+            continue;
+        }
+        verify_id(src, &mut d, diagnostics);
+        kept.push(d);
+    }
+    kept
+}
 
-    for object in objects {
-        continue_if!(object.is_empty());
-        let mut spl = object.split("=");
-        let definition = spl.next().unwrap();
+fn builtin_lines(mut builtins: Vec<TlDeclaration>) -> Vec<String> {
+    builtins.sort_by_key(|d| d.span.start);
+    builtins.iter().map(printer::declaration_line).collect()
+}
 
-        let category = spl.next().unwrap().trim().replace(";", "");
+/// fills `computed_id`, and `id` when the definition doesn't declare one
+fn verify_id(src: &str, d: &mut TlDeclaration, diagnostics: &mut Vec<TlDiagnostic>) {
+    let computed = constructor_id::compute_id(d);
+    d.computed_id = TlIdent::new(&constructor_id::format_id(computed));
+    match &d.id {
+        Some(declared) if constructor_id::parse_id(declared) != Some(computed) => {
+            diagnostics.push(TlDiagnostic::warning(src, d.span, format!("declared id `{declared}` of `{}` doesn't match the computed id `{}`", d.name, d.computed_id)));
+        }
+        Some(_) => {}
+        None => d.id = Some(d.computed_id.to_owned()),
+    }
+}
 
-        let mut spl = definition.split("#");
-        let name = spl.next().unwrap().trim().to_string();
+/// resets the doc block, keeping it as a type description if it was a `//@class` comment
+fn take_class_doc(doc: &mut TlDocComment, class_docs: &mut HashMap<String, Option<String>>) {
+    let doc = std::mem::take(doc);
+    if let Some(class) = doc.class() {
+        class_docs.insert(class, doc.get("description"));
+    }
+}
 
-        let name_spl = name.split(".").collect::<Vec<_>>();
-        let namespace = if name_spl.len() == 2 {
-            Some(name_spl[0].to_string())
-        } else {
-            None
-        };
-        let (id, parameters) = parse_parameter(&spl.collect::<Vec<_>>().join("#"));
-        let con = TlConstructor { parameters, id, name, namespace };
+fn parse_objects(layer_id: i32, objects: Vec<TlDeclaration>, class_docs: &HashMap<String, Option<String>>) -> Vec<TlType> {
+    let result_types = objects.iter().map(|o| o.result_type.ident()).collect::<Vec<_>>();
+    let constructors = tl_shared::share(layer_id, objects, |object| TlConstructor {
+        namespace: object.name.split_once('.').map(|(ns, _)| TlIdent::new(ns)),
+        id: object.id.unwrap_or_default(),
+        computed_id: object.computed_id,
+        name: object.name,
+        type_params: object.type_params,
+        flag_fields: tl_flag::flag_fields(&object.parameters),
+        parameters: object.parameters,
+        description: object.description,
+    });
 
-        map.entry(category).or_insert(vec![]).push(con);
+    let mut map = HashMap::new();
+    for (result_type, con) in result_types.into_iter().zip(constructors) {
+        map.entry(result_type).or_insert(vec![]).push(con);
     }
 
     map.into_iter()
-        .map(|(name, constructors)| TlType { constructors, name })
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(name, constructors)| {
+            let description = class_docs.get(name.as_str()).cloned().flatten();
+            TlType::new(name, description, constructors)
+        })
         .collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(src: &str) -> TlSchema {
+        parse_schema(TlLayer { family: SchemaFamily::Api, layer_id: 185, layer: src.to_owned(), release_date: Default::default() }).unwrap()
+    }
+
+    #[test]
+    fn ignores_the_mtproto_error_of_telegram_schemas() {
+        let schema = parse(include_str!("../../tests/fixtures/api_185.tl"));
+        assert_eq!(schema.dialect, TlDialect::Telegram);
+        assert!(schema.constructor("error", SchemaSection::All).is_none());
+        assert!(schema.api.builtins.contains(&"error#c4b9f9bb code:int text:string = Error;".to_owned()));
+    }
+
+    #[test]
+    fn keeps_the_error_object_of_tdlib_schemas() {
+        let schema = parse(include_str!("../../tests/fixtures/td_api.tl"));
+        assert_eq!(schema.dialect, TlDialect::Tdlib);
+        let error = schema.constructor("error", SchemaSection::All).expect("tdlib's error is a regular object");
+        assert_eq!(error.parameters.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["code", "message"]);
+        assert!(error.description.is_some());
+        assert!(schema.constructor("boolTrue", SchemaSection::All).is_none());
+    }
+}
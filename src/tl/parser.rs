@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use crate::tl::lexer::{Lexer, Token, TokenKind};
+use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_span::TlSpan;
+use crate::tl::tl_type_ref::TlTypeRef;
+use crate::tl::tl_ident::TlIdent;
+
+/// comment that separates the mtproto definitions from the application api
+pub const MAIN_API_MARKER: &str = "Main application API";
+
+#[derive(Debug)]
+pub struct TlParseError {
+    pub message: String,
+    pub span: TlSpan,
+}
+
+/// a single combinator, either a constructor or a function depending on the section it was found in
+#[derive(Debug)]
+pub struct TlDeclaration {
+    pub name: TlIdent,
+    pub id: Option<TlIdent>,
+    /// crc32 of the normalized definition, see [`crate::tl::constructor_id`]
+    pub computed_id: TlIdent,
+    pub type_params: Vec<TlIdent>,
+    pub parameters: Vec<TlParameter>,
+    pub result_type: TlTypeRef,
+    pub is_function: bool,
+    /// builtin declarations like `int ? = Int;`
+    pub is_builtin: bool,
+    /// filled from the doc comment above the declaration
+    pub description: Option<String>,
+    pub span: TlSpan,
+}
+
+#[derive(Debug)]
+pub enum TlStatement<'a> {
+    Comment { text: &'a str, span: TlSpan },
+    /// the `///////// Main application API` marker, everything before it is mtproto
+    ApiStart,
+    Declaration(TlDeclaration),
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedSchema<'a> {
+    pub statements: Vec<TlStatement<'a>>,
+    pub errors: Vec<TlParseError>,
+}
+
+pub fn parse(src: &str) -> ParsedSchema<'_> {
+    Parser { src, tokens: Lexer::tokenize(src), pos: 0, in_functions: false, idents: HashMap::new() }.parse()
+}
+
+/// a lone type expression like `Vector<User>`, `%Message` or `int128`
+pub fn parse_type(src: &str) -> Result<TlTypeRef, TlParseError> {
+    let mut parser = Parser { src, tokens: Lexer::tokenize(src), pos: 0, in_functions: false, idents: HashMap::new() };
+    if parser.peek().is_none() {
+        return Err(TlParseError { message: "expected a type".to_owned(), span: TlSpan::default() });
+    }
+    let _type = parser.type_expr().map_err(|e| match parser.pos < parser.tokens.len() {
+        true => e,
+        false => TlParseError { message: "unexpected end of the type".to_owned(), span: e.span },
+    })?;
+    match parser.peek() {
+        Some(token) => Err(parser.unexpected(token, "the end of the type")),
+        None => Ok(_type),
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    in_functions: bool,
+    /// identifiers already interned by this parser, saves a trip to the shared interner for repeated names
+    idents: HashMap<&'a str, TlIdent>,
+}
+
+type PResult<T> = Result<T, TlParseError>;
+
+impl<'a> Parser<'a> {
+    fn parse(mut self) -> ParsedSchema<'a> {
+        let mut parsed = ParsedSchema::default();
+        while let Some(token) = self.tokens.get(self.pos).copied() {
+            match token.kind {
+                TokenKind::Comment(text) => {
+                    self.pos += 1;
+                    if text.contains(MAIN_API_MARKER) {
+                        self.in_functions = false;
+                        parsed.statements.push(TlStatement::ApiStart);
+                    } else {
+                        parsed.statements.push(TlStatement::Comment { text, span: token.span });
+                    }
+                }
+                TokenKind::Section(name) => {
+                    self.pos += 1;
+                    match name {
+                        "types" => self.in_functions = false,
+                        "functions" => self.in_functions = true,
+                        _ => parsed.errors.push(TlParseError { message: format!("unknown section `---{name}---`"), span: token.span }),
+                    }
+                }
+                _ => {
+                    let start = self.pos;
+                    match self.declaration() {
+                        Ok(declaration) => parsed.statements.push(TlStatement::Declaration(declaration)),
+                        Err(e) => {
+                            parsed.errors.push(e);
+                            self.recover(start);
+                        }
+                    }
+                }
+            }
+        }
+        parsed
+    }
+
+    /// skips the rest of a broken declaration. stops after the next `;`, before a section marker
+    /// or before a line that looks like the start of another declaration (`name#id`)
+    fn recover(&mut self, start: usize) {
+        if self.pos == start {
+            self.pos += 1;
+        }
+        while let Some(token) = self.tokens.get(self.pos) {
+            match token.kind {
+                TokenKind::Semicolon => {
+                    self.pos += 1;
+                    return;
+                }
+                TokenKind::Section(_) => return,
+                _ if self.starts_declaration(self.pos) => return,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn starts_declaration(&self, i: usize) -> bool {
+        let (Some(token), Some(next)) = (self.tokens.get(i), self.tokens.get(i + 1)) else {
+            return false;
+        };
+        let first_on_line = i == 0 || self.tokens[i - 1].span.line < token.span.line;
+        first_on_line
+            && matches!(token.kind, TokenKind::Word(_))
+            && next.kind == TokenKind::Hash
+            && next.span.start == token.span.end
+    }
+
+    fn skip_comments(&mut self) {
+        while self.tokens.get(self.pos).is_some_and(|t| matches!(t.kind, TokenKind::Comment(_))) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<Token<'a>> {
+        self.skip_comments();
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn peek_kind(&mut self) -> Option<TokenKind<'a>> {
+        self.peek().map(|t| t.kind)
+    }
+
+    /// looks `n` tokens ahead of the current one, comments are not skipped
+    fn peek_nth(&self, n: usize) -> Option<TokenKind<'a>> {
+        self.tokens.get(self.pos + n).map(|t| t.kind)
+    }
+
+    fn next(&mut self) -> PResult<Token<'a>> {
+        let token = self.peek().ok_or_else(|| self.eof_error())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn prev_span(&self) -> TlSpan {
+        self.tokens.get(self.pos.wrapping_sub(1)).map(|t| t.span).unwrap_or_default()
+    }
+
+    fn eof_error(&self) -> TlParseError {
+        TlParseError { message: "unexpected end of schema, missing `;`".to_owned(), span: self.prev_span() }
+    }
+
+    fn unexpected(&self, token: Token, expected: &str) -> TlParseError {
+        TlParseError { message: format!("expected {expected}, found {}", describe(token.kind)), span: token.span }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> PResult<Token<'a>> {
+        let token = self.peek().ok_or_else(|| self.eof_error())?;
+        if token.kind != kind {
+            return Err(self.unexpected(token, expected));
+        }
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn word(&mut self, expected: &str) -> PResult<(&'a str, TlSpan)> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Word(w) => Ok((w, token.span)),
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected(token, expected))
+            }
+        }
+    }
+
+    fn ident(&mut self, s: &'a str) -> TlIdent {
+        self.idents.entry(s).or_insert_with(|| TlIdent::new(s)).clone()
+    }
+
+    /// `name` or `namespace.name`
+    fn full_name(&mut self, expected: &str) -> PResult<(TlIdent, TlSpan)> {
+        let (first, span) = self.word(expected)?;
+        if self.peek_nth(0) == Some(TokenKind::Dot) && matches!(self.peek_nth(1), Some(TokenKind::Word(_))) {
+            self.pos += 1;
+            let (second, end) = self.word(expected)?;
+            //`ns.name` is written without spaces almost everywhere, so it can be taken from the source as is
+            let full = if end.start == span.end + 1 {
+                self.ident(&self.src[span.start..end.end])
+            } else {
+                TlIdent::new(&format!("{first}.{second}"))
+            };
+            return Ok((full, span.to(end)));
+        }
+        Ok((self.ident(first), span))
+    }
+
+    fn declaration(&mut self) -> PResult<TlDeclaration> {
+        let (name, name_span) = self.full_name("a definition name")?;
+
+        let id = match self.peek() {
+            Some(t) if t.kind == TokenKind::Hash && t.span.start == name_span.end => {
+                self.pos += 1;
+                let (id, span) = self.word("a constructor id")?;
+                if span.start != t.span.end || !id.chars().all(|c| c.is_ascii_hexdigit()) || id.len() > 8 {
+                    return Err(TlParseError { message: format!("invalid constructor id `{id}`"), span });
+                }
+                Some(self.ident(id))
+            }
+            _ => None,
+        };
+
+        let mut type_params = vec![];
+        let mut parameters = vec![];
+        let mut is_builtin = false;
+        loop {
+            match self.peek_kind() {
+                Some(TokenKind::Equals) => break,
+                Some(TokenKind::Question) => {
+                    self.pos += 1;
+                    is_builtin = true;
+                }
+                Some(TokenKind::LBrace) => type_params.push(self.type_param()?),
+                Some(TokenKind::Section(_)) | None => {
+                    return Err(self.peek().map(|t| self.unexpected(t, "`=`")).unwrap_or_else(|| self.eof_error()));
+                }
+                Some(_) => parameters.push(self.parameter()?),
+            }
+        }
+        self.expect(TokenKind::Equals, "`=`")?;
+        let result_type = self.result_type()?;
+        let end = self.expect(TokenKind::Semicolon, "`;`")?;
+
+        Ok(TlDeclaration {
+            name,
+            id,
+            computed_id: TlIdent::default(),
+            type_params,
+            parameters,
+            result_type,
+            is_function: self.in_functions,
+            is_builtin,
+            description: None,
+            span: name_span.to(end.span),
+        })
+    }
+
+    /// `{X:Type}`
+    fn type_param(&mut self) -> PResult<TlIdent> {
+        self.expect(TokenKind::LBrace, "`{`")?;
+        let (name, _) = self.word("a type parameter name")?;
+        self.expect(TokenKind::Colon, "`:`")?;
+        self.type_expr()?;
+        self.expect(TokenKind::RBrace, "`}`")?;
+        Ok(self.ident(name))
+    }
+
+    fn parameter(&mut self) -> PResult<TlParameter> {
+        let start = self.peek().ok_or_else(|| self.eof_error())?;
+
+        let named = matches!(start.kind, TokenKind::Word(_)) && self.peek_nth(1) == Some(TokenKind::Colon);
+        if !named {
+            return self.anonymous_parameter(start);
+        }
+        let (name, _) = self.word("a parameter name")?;
+        let name = self.ident(name);
+        self.pos += 1;
+
+        if self.peek_kind() == Some(TokenKind::LBracket) {
+            let _type = self.repetition()?;
+            return Ok(TlParameter::new(name, _type, start.span.to(self.prev_span())));
+        }
+
+        let is_condition = matches!(self.peek_nth(0), Some(TokenKind::Word(_)))
+            && self.peek_nth(1) == Some(TokenKind::Dot)
+            && matches!(self.peek_nth(2), Some(TokenKind::Word(_)))
+            && self.peek_nth(3) == Some(TokenKind::Question);
+
+        let _type = if is_condition {
+            let (field, _) = self.word("a flag field")?;
+            self.pos += 1;
+            let (bit, bit_span) = self.word("a flag bit")?;
+            let Some(bit) = bit.parse::<u8>().ok().filter(|b| *b < 32) else {
+                return Err(TlParseError { message: format!("invalid flag bit `{bit}`"), span: bit_span });
+            };
+            self.pos += 1;
+            TlTypeRef::Flag { field: self.ident(field), bit, inner: Box::new(self.type_expr()?) }
+        } else {
+            self.type_expr()?
+        };
+        Ok(TlParameter::new(name, _type, start.span.to(self.prev_span())))
+    }
+
+    /// unnamed arguments, only used by builtins (`# [ t ]`, `4*[ int ]`)
+    fn anonymous_parameter(&mut self, start: Token<'a>) -> PResult<TlParameter> {
+        let _type = match start.kind {
+            TokenKind::LBracket => self.repetition()?,
+            TokenKind::Word(n) if self.peek_nth(1) == Some(TokenKind::Star) => {
+                self.pos += 2;
+                let TlTypeRef::Bare { name } = self.repetition()? else { unreachable!() };
+                TlTypeRef::Bare { name: TlIdent::new(&format!("{n}*{name}")) }
+            }
+            TokenKind::Word(_) | TokenKind::Hash | TokenKind::Percent => self.type_expr()?,
+            _ => return Err(self.unexpected(start, "a parameter")),
+        };
+        Ok(TlParameter::new(TlIdent::default(), _type, start.span.to(self.prev_span())))
+    }
+
+    /// `[ t ]`. repetitions only appear in builtins, so they are kept as text
+    fn repetition(&mut self) -> PResult<TlTypeRef> {
+        self.expect(TokenKind::LBracket, "`[`")?;
+        let mut items = vec![];
+        while self.peek_kind() != Some(TokenKind::RBracket) {
+            let param = self.parameter()?;
+            items.push(if param.name.is_empty() { param._type.to_string() } else { format!("{}:{}", param.name, param._type) });
+        }
+        self.pos += 1;
+        Ok(TlTypeRef::Bare { name: TlIdent::new(&format!("[ {} ]", items.join(" "))) })
+    }
+
+    /// `Type`, `ns.Type`, `%Type`, `#`, `!X` or `Vector<Type>`
+    fn type_expr(&mut self) -> PResult<TlTypeRef> {
+        let token = self.peek().ok_or_else(|| self.eof_error())?;
+        match token.kind {
+            TokenKind::Hash => {
+                self.pos += 1;
+                Ok(TlTypeRef::Nat)
+            }
+            TokenKind::Bang => {
+                self.pos += 1;
+                let (name, _) = self.full_name("a type variable")?;
+                Ok(TlTypeRef::Generic { name })
+            }
+            TokenKind::Percent => {
+                self.pos += 1;
+                let (name, _) = self.full_name("a type name")?;
+                Ok(TlTypeRef::Bare { name })
+            }
+            TokenKind::Word(_) => {
+                let (name, span) = self.full_name("a type name")?;
+                if self.peek_kind() != Some(TokenKind::LAngle) {
+                    return Ok(TlTypeRef::named(name));
+                }
+                self.pos += 1;
+                let inner = self.type_expr()?;
+                self.expect(TokenKind::RAngle, "`>`")?;
+                vector(name, inner, span)
+            }
+            _ => Err(self.unexpected(token, "a type")),
+        }
+    }
+
+    /// the part after `=`, also accepts the space separated form `Vector t`
+    fn result_type(&mut self) -> PResult<TlTypeRef> {
+        let start = self.peek().map(|t| t.span).unwrap_or_default();
+        let result = self.type_expr()?;
+        let Some(token) = self.peek().filter(|t| matches!(t.kind, TokenKind::Word(_)) && t.span.line == start.line) else {
+            return Ok(result);
+        };
+        let TlTypeRef::Boxed { name } = result else {
+            return Err(self.unexpected(token, "`;`"));
+        };
+        let inner = self.type_expr()?;
+        vector(name, inner, start)
+    }
+}
+
+/// `Vector` is the only type that takes an argument
+fn vector(name: TlIdent, inner: TlTypeRef, span: TlSpan) -> PResult<TlTypeRef> {
+    match name.as_str() {
+        "Vector" => Ok(TlTypeRef::Vector { bare: false, inner: Box::new(inner) }),
+        "vector" => Ok(TlTypeRef::Vector { bare: true, inner: Box::new(inner) }),
+        _ => Err(TlParseError { message: format!("`{name}` doesn't take type arguments"), span }),
+    }
+}
+
+fn describe(kind: TokenKind) -> String {
+    match kind {
+        TokenKind::Word(w) => format!("`{w}`"),
+        TokenKind::Comment(_) => "a comment".to_owned(),
+        TokenKind::Section(s) => format!("`---{s}---`"),
+        TokenKind::Hash => "`#`".to_owned(),
+        TokenKind::Colon => "`:`".to_owned(),
+        TokenKind::Semicolon => "`;`".to_owned(),
+        TokenKind::Equals => "`=`".to_owned(),
+        TokenKind::Question => "`?`".to_owned(),
+        TokenKind::Bang => "`!`".to_owned(),
+        TokenKind::Percent => "`%`".to_owned(),
+        TokenKind::Dot => "`.`".to_owned(),
+        TokenKind::Comma => "`,`".to_owned(),
+        TokenKind::Star => "`*`".to_owned(),
+        TokenKind::LAngle => "`<`".to_owned(),
+        TokenKind::RAngle => "`>`".to_owned(),
+        TokenKind::LBrace => "`{`".to_owned(),
+        TokenKind::RBrace => "`}`".to_owned(),
+        TokenKind::LBracket => "`[`".to_owned(),
+        TokenKind::RBracket => "`]`".to_owned(),
+        TokenKind::Unknown(c) => format!("unexpected character `{c}`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declarations<'a>(parsed: &'a ParsedSchema) -> Vec<&'a str> {
+        parsed.statements.iter().filter_map(|s| match s {
+            TlStatement::Declaration(d) => Some(d.name.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn parses_flags_vectors_and_type_params() {
+        let parsed = parse("---functions---\ninvokeWithLayer#da9b0d0d {X:Type} layer:int query:!X = X;\nusers.getUsers#d91a548 flags:# id:flags.0?Vector<InputUser> = Vector<User>;");
+        assert!(parsed.errors.is_empty());
+        let [TlStatement::Declaration(invoke), TlStatement::Declaration(get_users)] = parsed.statements.as_slice() else {
+            panic!("expected two declarations, got {:?}", parsed.statements);
+        };
+        assert!(invoke.is_function);
+        assert_eq!(invoke.type_params, vec![TlIdent::new("X")]);
+        assert_eq!(invoke.parameters[1]._type, TlTypeRef::Generic { name: TlIdent::new("X") });
+        assert_eq!(get_users.name, "users.getUsers");
+        assert_eq!(get_users.parameters[1]._type.to_string(), "flags.0?Vector<InputUser>");
+        assert_eq!(get_users.result_type.to_string(), "Vector<User>");
+    }
+
+    #[test]
+    fn recovers_after_a_broken_declaration() {
+        let parsed = parse("first#1 = A;\nbroken#2 x: = A;\nsecond#3 y:int = A;");
+        assert_eq!(declarations(&parsed), vec!["first", "second"]);
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].span.line, 2);
+        assert!(parsed.errors[0].message.contains("expected a type"), "{}", parsed.errors[0].message);
+    }
+
+    #[test]
+    fn recovers_at_the_next_declaration_when_a_semicolon_is_missing() {
+        let parsed = parse("first#1 = A\nsecond#2 = A;");
+        assert_eq!(declarations(&parsed), vec!["second"]);
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].span.line, 2);
+    }
+
+    #[test]
+    fn reports_invalid_ids_sections_and_the_end_of_the_schema() {
+        let parsed = parse("bad#xyz = A;\n---methods---\ngood#1 = A;\nlast#2 = A");
+        assert_eq!(declarations(&parsed), vec!["good"]);
+        let messages = parsed.errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>();
+        assert_eq!(messages, vec!["invalid constructor id `xyz`", "unknown section `---methods---`", "unexpected end of schema, missing `;`"]);
+    }
+
+    #[test]
+    fn parse_type_rejects_trailing_tokens() {
+        assert_eq!(parse_type("Vector<%Message>").unwrap().to_string(), "Vector<%Message>");
+        assert!(parse_type("Vector<User> x").is_err());
+        assert!(parse_type("").is_err());
+    }
+}
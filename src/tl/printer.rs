@@ -0,0 +1,200 @@
+use std::fmt::Write;
+use itertools::Itertools;
+use crate::models::requests::SchemaSection;
+use crate::tl::{TlDialect, TlSchema, TlSection};
+use crate::tl::parser::TlDeclaration;
+use crate::tl::tl_constructor::TlConstructor;
+use crate::tl::tl_function::TlFunction;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_type::TlType;
+
+/// `name#id {X:Type} param:type = Type;`
+pub fn constructor_line(ctor: &TlConstructor, type_name: &str) -> String {
+    definition_line(&ctor.name, &ctor.id, &ctor.type_params, &ctor.parameters, type_name)
+}
+
+pub fn function_line(function: &TlFunction) -> String {
+    definition_line(&function.name, &function.id, &function.type_params, &function.parameters, &function.return_type.to_string())
+}
+
+/// tdlib files don't write ids, the ones of a tdlib schema are computed
+fn written_id(id: &str, dialect: TlDialect) -> &str {
+    if dialect == TlDialect::Tdlib { "" } else { id }
+}
+
+/// a declaration as it was parsed, builtins keep their `?`
+pub fn declaration_line(d: &TlDeclaration) -> String {
+    if d.is_builtin {
+        return format!("{} ? = {};", d.name, d.result_type);
+    }
+    definition_line(&d.name, d.id.as_deref().unwrap_or_default(), &d.type_params, &d.parameters, &d.result_type.to_string())
+}
+
+fn definition_line(name: &str, id: &str, type_params: &[TlIdent], parameters: &[TlParameter], result: &str) -> String {
+    let mut line = if id.is_empty() { name.to_owned() } else { format!("{name}#{id}") };
+    for t in type_params {
+        let _ = write!(line, " {{{t}:Type}}");
+    }
+    for p in parameters {
+        let _ = if p.name.is_empty() { write!(line, " {}", p._type) } else { write!(line, " {}:{}", p.name, p._type) };
+    }
+    let _ = write!(line, " = {result};");
+    line
+}
+
+/// `//@description ... @param ...` above a definition, `None` if nothing is documented
+fn doc_comment(description: &Option<String>, parameters: &[TlParameter]) -> Option<String> {
+    let mut fields = vec![];
+    if let Some(d) = description {
+        fields.push(format!("@description {d}"));
+    }
+    for p in parameters {
+        let Some(d) = &p.description else { continue };
+        let key = if p.name == "description" { "param_description" } else { &p.name };
+        fields.push(format!("@{key} {d}"));
+    }
+    if fields.is_empty() { None } else { Some(format!("//{}", fields.join(" "))) }
+}
+
+fn push_definition(out: &mut String, doc: Option<String>, line: String) {
+    if let Some(doc) = doc {
+        out.push_str(&doc);
+        out.push('\n');
+    }
+    out.push_str(&line);
+    out.push('\n');
+}
+
+/// every constructor of a type, in source order
+pub fn type_text(tl_type: &TlType) -> String {
+    dialect_type_text(tl_type, TlDialect::Telegram)
+}
+
+fn dialect_type_text(tl_type: &TlType, dialect: TlDialect) -> String {
+    let mut out = String::new();
+    if let Some(d) = &tl_type.description {
+        let _ = writeln!(out, "//@class {} @description {d}\n", tl_type.name);
+    }
+    for ctor in tl_type.constructors.iter().sorted_by_key(|c| c.span.start) {
+        let line = definition_line(&ctor.name, written_id(&ctor.id, dialect), &ctor.type_params, &ctor.parameters, &tl_type.name);
+        push_definition(&mut out, doc_comment(&ctor.description, &ctor.parameters), line);
+    }
+    out
+}
+
+fn section_text(section: &TlSection, dialect: TlDialect) -> String {
+    let mut out = String::from("---types---\n\n");
+    for line in &section.builtins {
+        out.push_str(line);
+        out.push('\n');
+    }
+    if !section.builtins.is_empty() {
+        out.push('\n');
+    }
+    let types = section.objects
+        .iter()
+        .sorted_by_key(|t| t.constructors.iter().map(|c| c.span.start).min());
+    for tl_type in types {
+        out.push_str(&dialect_type_text(tl_type, dialect));
+        out.push('\n');
+    }
+
+    out.push_str("---functions---\n\n");
+    let functions = section.functions
+        .values()
+        .flatten()
+        .sorted_by_key(|f| f.span.start);
+    for function in functions {
+        let line = definition_line(&function.name, written_id(&function.id, dialect), &function.type_params, &function.parameters, &function.return_type.to_string());
+        push_definition(&mut out, doc_comment(&function.description, &function.parameters), line);
+    }
+    out
+}
+
+/// canonical `.tl` text of a layer, parsing it again gives back the same definitions
+pub fn schema_text(schema: &TlSchema, section: SchemaSection) -> String {
+    match section {
+        //tdlib doesn't have an mtproto part, printing the marker would turn it into a telegram schema
+        _ if schema.dialect == TlDialect::Tdlib => section_text(&schema.api, schema.dialect),
+        SchemaSection::Api => section_text(&schema.api, schema.dialect),
+        SchemaSection::Mtproto => section_text(&schema.mtproto, schema.dialect),
+        SchemaSection::All => format!("{}\n///////// Main application API\n\n{}", section_text(&schema.mtproto, schema.dialect), section_text(&schema.api, schema.dialect)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use super::*;
+    use crate::models::requests::SchemaFamily;
+    use crate::models::tl_layer::TlLayer;
+    use crate::tl;
+
+    fn parse(src: &str) -> TlSchema {
+        tl::parse_schema(TlLayer { family: SchemaFamily::Api, layer_id: 185, layer: src.to_owned(), release_date: Default::default() }).unwrap()
+    }
+
+    /// every definition as json without its position, positions are the only thing printing may change
+    fn definitions(schema: &TlSchema) -> BTreeMap<String, Value> {
+        fn without_spans<T: Serialize>(t: &T) -> Value {
+            fn strip(v: &mut Value) {
+                match v {
+                    Value::Object(o) => {
+                        o.remove("span");
+                        o.values_mut().for_each(strip);
+                    }
+                    Value::Array(a) => a.iter_mut().for_each(strip),
+                    _ => {}
+                }
+            }
+            let mut v = serde_json::to_value(t).unwrap();
+            strip(&mut v);
+            v
+        }
+
+        let mut out = BTreeMap::new();
+        for (name, section) in [("api", &schema.api), ("mtproto", &schema.mtproto)] {
+            for t in &section.objects {
+                out.insert(format!("{name} {}", t.name), without_spans(t));
+            }
+            for f in section.functions.values().flatten() {
+                out.insert(format!("{name} {}", f.name), without_spans(f));
+            }
+            out.insert(format!("{name} builtins"), json!(section.builtins));
+        }
+        out
+    }
+
+    fn assert_round_trips(src: &str) {
+        let schema = parse(src);
+        let printed = schema_text(&schema, SchemaSection::All);
+        let reparsed = parse(&printed);
+        assert_eq!(reparsed.dialect, schema.dialect);
+        assert_eq!(definitions(&reparsed), definitions(&schema));
+        assert_eq!(schema_text(&reparsed, SchemaSection::All), printed);
+    }
+
+    #[test]
+    fn round_trips_a_telegram_layer() {
+        assert_round_trips(include_str!("../../tests/fixtures/api_185.tl"));
+    }
+
+    #[test]
+    fn round_trips_a_tdlib_layer() {
+        assert_round_trips(include_str!("../../tests/fixtures/td_api.tl"));
+    }
+
+    #[test]
+    fn keeps_builtins_and_hardcoded_definitions() {
+        let schema = parse(include_str!("../../tests/fixtures/api_185.tl"));
+        let printed = schema_text(&schema, SchemaSection::All);
+        for line in ["int ? = Int;", "vector#1cb5c415 {t:Type} # [ t ] = Vector<t>;", "int128 4*[ int ] = Int128;", "boolTrue#997275b5 = Bool;", "null#56730bcc = Null;"] {
+            assert!(printed.contains(line), "`{line}` is missing from\n{printed}");
+        }
+        assert!(schema.mtproto.builtins.contains(&"int ? = Int;".to_owned()));
+        assert!(schema.api.builtins.contains(&"boolTrue#997275b5 = Bool;".to_owned()));
+    }
+}
@@ -1,31 +1,74 @@
-use std::collections::HashSet;
-use std::time::Duration;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use dotenv::var;
 use itertools::Itertools;
-use meilisearch_sdk::{client::Client, search::{SearchResults, Selectors}, task_info::TaskInfo};
+use rayon::prelude::*;
+use meilisearch_sdk::{client::Client, documents::DocumentsQuery, indexes::Index, search::{SearchResults, Selectors}, task_info::TaskInfo};
 use serde::de::DeserializeOwned;
 use crate::{
+    continue_if,
     models::{
-        responses::{CompactTlDefinitionResponse, FunctionHistory, FunctionHistoryResponse, GetFuncResponse, GetFunction, GetObject, GetObjectResponse, HistoryResponse, Namespace, ObjectHistory, ObjectHistoryResponse, ObjectUsage, SearchResponse, TypeResponse},
+        responses::{CompactTlDefinitionResponse, FunctionHistory, FunctionHistoryResponse, GetFuncResponse, GetFunction, GetObject, GetObjectResponse, HistoryResponse, IdHit, Namespace, ObjectHistory, ObjectHistoryResponse, ObjectUsage, SearchResponse, TypeResponse},
         layer_release_date::LayerReleaseDate,
         tl_layer::TlLayer,
-        requests::{FetchMode, GetByNameRequest, GetNamespaceRequest, SearchLayerRequest},
+        requests::{DecodeRequest, EncodeRequest, FetchMode, GetByNameRequest, InspectRequest, GetNamespaceRequest, HistoryRequest, SchemaFamily, SchemaSection, SearchLayerRequest},
         compact_schema::{CompactTlConstructor, CompactTlDefinition, DefinitionType},
     },
     tl::{
         self,
+        printer,
+        changelog::TlChangelog,
+        codec::{self, TlCodec},
+        inspector::{self, PayloadReport},
+        impact::Classify,
+        layer_diff::{self, TlLayerDiff},
+        lint::{self, LintConfig, LintReport},
+        matcher::{self, TlCandidate},
         tl_constructor::TlConstructor,
         tl_function::TlFunction,
+        tl_graph::TlGraph,
+        tl_ident::TlIdent,
         tl_parameter::TlParameter,
+        tl_type_ref::TlTypeRef,
+        tl_shared::{self, TlShared},
+        TlDialect,
         TlSchema,
     },
 };
 use crate::models::compact_schema::RefCompactTlConstructor;
-use crate::models::responses::{GetTypeCompact, GetTypeFull, GetTypeResponse};
+use crate::models::responses::{FlagFieldLayout, FlagLayoutResponse, GetLayerResponse, GetTypeCompact, GetTypeFull, GetTypeResponse};
+
+/// a definition of an id in one layer
+struct IdEntry {
+    /// index into `schemas`
+    schema: usize,
+    section: SchemaSection,
+    definition: IdDefinition,
+}
+
+enum IdDefinition {
+    Constructor { tl_type: TlIdent, constructor: TlShared<TlConstructor> },
+    Function(TlShared<TlFunction>),
+}
+
+impl IdDefinition {
+    fn id(&self) -> &str {
+        match self {
+            IdDefinition::Constructor { constructor, .. } => &constructor.id,
+            IdDefinition::Function(function) => &function.id,
+        }
+    }
+}
 
 pub struct SchemaManager {
     schemas: Vec<TlSchema>,
     compact_definitions: Vec<CompactTlDefinition>,
+    /// every definition of an id, in the order of `schemas`
+    ids: HashMap<u32, Vec<IdEntry>>,
+    /// dependency graphs of `schemas` by section, built the first time they are asked for
+    graphs: Vec<[OnceLock<TlGraph>; 3]>,
     meilisearch: Client,
     init_task_info: TaskInfo,
 }
@@ -33,17 +76,22 @@ pub struct SchemaManager {
 
 impl SchemaManager {
     pub async fn new(layers: Vec<TlLayer>, meilisearch: Client) -> eyre::Result<Self> {
-        let mut schemas = layers
-            .into_iter()
-            .map(tl::parse_schema)
-            .collect::<Vec<_>>();
+        let started = Instant::now();
+        let count = layers.len();
+        let schemas = tokio::task::block_in_place(|| Self::parse_layers(layers));
+        log::info!("parsed {} of {count} layers in {:?}", schemas.len(), started.elapsed());
 
-        schemas.sort_by(|s, s2| s.layer_id.cmp(&s2.layer_id));
+        let phase = Instant::now();
+        let compact_definitions = tokio::task::block_in_place(|| Self::create_compact_definitions(&schemas));
+        log::info!("created {} compact definitions in {:?}", compact_definitions.len(), phase.elapsed());
 
-        let compact_definitions = Self::create_compact_definitions(&schemas);
+        let phase = Instant::now();
         let index = meilisearch.index("schema");
-
-        let task_info = if var("REPLACE_DATA")?.parse::<bool>().unwrap_or(false) {
+        let outdated = Self::index_is_outdated(&index).await;
+        if outdated {
+            log::warn!("the search index was built without the `family` field, rebuilding it");
+        }
+        let task_info = if outdated || var("REPLACE_DATA")?.parse::<bool>().unwrap_or(false) {
             let info = meilisearch.delete_index("schema").await?;
             info.wait_for_completion(&meilisearch, Some(Duration::from_secs(2)), Some(Duration::from_secs(10))).await?;
             index.add_documents(&compact_definitions, Some("id")).await?
@@ -51,58 +99,99 @@ impl SchemaManager {
             log::trace!("skipped index creation");
             crate::prelude::DEFAULT_TASK_INFO
         };
-        println!("finished initializing");
-        index.set_filterable_attributes(["layer_id", "definition_id", "name", "definition_type", "return_type", "namespace"]).await?;
-        Ok(Self { schemas, compact_definitions, meilisearch, init_task_info: task_info })
+        index.set_filterable_attributes(["family", "layer_id", "definition_id", "name", "definition_type", "return_type", "namespace", "section", "dialect"]).await?;
+        log::info!("queued the search index in {:?}", phase.elapsed());
+        log::info!("finished initializing in {:?}", started.elapsed());
+        Ok(Self { ids: Self::index_ids(&schemas), graphs: schemas.iter().map(|_| Default::default()).collect(), schemas, compact_definitions, meilisearch, init_task_info: task_info })
+    }
+
+    /// indexes built before schema families don't have `family`, every search filters on it
+    async fn index_is_outdated(index: &Index) -> bool {
+        let filterable = index.get_filterable_attributes().await.unwrap_or_default();
+        let documents = index.get_documents_with::<serde_json::Map<String, serde_json::Value>>(DocumentsQuery::new(index).with_limit(1).with_fields(["family"])).await;
+        match documents {
+            Ok(documents) => !filterable.iter().any(|a| a == "family") || documents.results.iter().any(|d| !d.contains_key("family")),
+            //no index yet
+            Err(_) => true,
+        }
+    }
+
+    /// parses the layers on every core, the result is sorted by family and layer whichever finishes first
+    fn parse_layers(layers: Vec<TlLayer>) -> Vec<TlSchema> {
+        let parsed = layers.into_par_iter()
+            .map(|layer| (layer.family, layer.layer_id, tl::parse_schema(layer)))
+            .collect::<Vec<_>>();
+
+        let mut schemas = vec![];
+        for (family, layer_id, result) in parsed {
+            match result {
+                Ok(schema) => {
+                    for d in &schema.diagnostics {
+                        log::warn!("{family} layer {layer_id}: {d}");
+                    }
+                    schemas.push(schema);
+                }
+                Err(diagnostics) => {
+                    for d in &diagnostics {
+                        log::error!("{family} layer {layer_id}: {d}");
+                    }
+                    log::error!("skipped {family} layer {layer_id}, it could not be parsed");
+                }
+            }
+        }
+
+        schemas.sort_by_key(|s| (s.family, s.layer_id));
+        tl_shared::track_unchanged(&mut schemas);
+        schemas
     }
 
     pub fn get_types(&self, req: &GetByNameRequest) -> GetTypeResponse {
         let limit = req.limit.unwrap_or(30);
-        let layers_to_iter = self.filter_schema_by_id(req.layer_id.map(|a| a as i32));
+        let layers_to_iter = self.filter_schema_by_id(req.family, req.layer_id.map(|a| a as i32));
 
         match req.mode {
             FetchMode::Compact => {
                 let mut _types = vec![];
                 for layer in layers_to_iter {
-                    let Some(tl_type) = layer.objects.iter().find(|a| a.name.eq(&req.name)) else {
+                    let Some(tl_type) = layer.objects(req.section).find(|a| a.name.eq(&req.name)) else {
                         continue;
                     };
                     let objects = tl_type.constructors
                         .iter()
                         .take(limit)
-                        .map(RefCompactTlConstructor::from)
+                        .map(|c| RefCompactTlConstructor::from(&**c))
                         .collect();
-                    _types.push(GetTypeCompact { objects, layer_id: layer.layer_id });
+                    _types.push(GetTypeCompact { objects, layer_id: layer.layer_id, description: &tl_type.description, common_parameters: &tl_type.common_parameters });
                 }
                 GetTypeResponse::CompactMode(_types)
             }
             FetchMode::Full => {
                 let mut _types = vec![];
                 for layer in layers_to_iter {
-                    let Some(tl_type) = layer.objects.iter().find(|a| a.name.eq(&req.name)) else {
+                    let Some(tl_type) = layer.objects(req.section).find(|a| a.name.eq(&req.name)) else {
                         continue;
                     };
                     if _types.len() >= limit { break; }
-                    _types.push(GetTypeFull { layer_id: layer.layer_id, objects: &tl_type.constructors });
+                    _types.push(GetTypeFull { layer_id: layer.layer_id, description: &tl_type.description, objects: &tl_type.constructors, common_parameters: &tl_type.common_parameters, tl: printer::type_text(tl_type) });
                 }
                 GetTypeResponse::FullMode(_types)
             }
         }
     }
-    pub fn get_type_names(&self, layer_id: Option<i32>) -> Vec<TypeResponse> {
-        self.filter_schema_by_id(layer_id)
+    pub fn get_type_names(&self, family: SchemaFamily, layer_id: Option<i32>, section: SchemaSection) -> Vec<TypeResponse> {
+        self.filter_schema_by_id(family, layer_id)
             .iter()
-            .map(|f| TypeResponse { layer_id: f.layer_id, types: f.objects.iter().map(|a| &a.name).collect() })
+            .map(|f| TypeResponse { layer_id: f.layer_id, types: f.objects(section).map(|a| &a.name).collect() })
             .collect()
     }
 
-    pub fn get_namespace(&self, layer_id: Option<i32>) -> Vec<Namespace> {
-        let layers_to_iter = self.filter_schema_by_id(layer_id);
+    pub fn get_namespace(&self, family: SchemaFamily, layer_id: Option<i32>, section: SchemaSection) -> Vec<Namespace> {
+        let layers_to_iter = self.filter_schema_by_id(family, layer_id);
 
         let mut res = vec![];
         for schema in layers_to_iter {
-            let mut map = HashSet::<&String>::new();
-            for tl_type in &schema.objects {
+            let mut map = HashSet::<&TlIdent>::new();
+            for tl_type in schema.objects(section) {
                 for ctor in &tl_type.constructors {
                     if let Some(ns) = &ctor.namespace {
                         map.insert(ns);
@@ -111,24 +200,27 @@ impl SchemaManager {
             }
             res.push(Namespace {
                 layer_id: schema.layer_id as _,
-                function_ns: schema.functions.keys().collect(),
+                function_ns: schema.functions(section).map(|(ns, _)| ns).unique().collect(),
                 object_ns: map.into_iter().collect::<Vec<_>>(),
             });
         }
         res
     }
 
-    pub fn get_namespace_functions(&self, req: &GetNamespaceRequest) -> Option<&Vec<TlFunction>> {
-        let li = req.layer_id as i32;
-        let schema = self.schemas.iter().find(|f| f.layer_id == li)?;
-        schema.functions.get(&req.namespace)
+    pub fn get_namespace_functions(&self, req: &GetNamespaceRequest) -> Option<Vec<&TlShared<TlFunction>>> {
+        let schema = self.schema(req.family, req.layer_id as _)?;
+        let functions = schema.sections(req.section)
+            .into_iter()
+            .filter_map(|s| s.functions.get(req.namespace.as_str()))
+            .flatten()
+            .collect::<Vec<_>>();
+        if functions.is_empty() { None } else { Some(functions) }
     }
 
-    pub fn get_namespace_objects(&self, req: &GetNamespaceRequest) -> Option<Vec<&TlConstructor>> {
-        let li = req.layer_id as i32;
-        let schema = self.schemas.iter().find(|f| f.layer_id == li)?;
+    pub fn get_namespace_objects(&self, req: &GetNamespaceRequest) -> Option<Vec<&TlShared<TlConstructor>>> {
+        let schema = self.schema(req.family, req.layer_id as _)?;
         let mut res = vec![];
-        for tl_type in &schema.objects {
+        for tl_type in schema.objects(req.section) {
             for ctor in &tl_type.constructors {
                 if ctor.namespace.as_ref().is_some_and(|f| f.eq(&req.namespace)) {
                     res.push(ctor);
@@ -138,10 +230,10 @@ impl SchemaManager {
         Some(res)
     }
 
-    pub fn history(&self, name: &str, definition_type: DefinitionType) -> HistoryResponse {
+    pub fn history(&self, req: &HistoryRequest, definition_type: DefinitionType) -> HistoryResponse {
         match definition_type {
-            DefinitionType::Function => self.get_function_history(name).map(HistoryResponse::Function),
-            DefinitionType::Object => self.get_object_history(name).map(HistoryResponse::Object)
+            DefinitionType::Function => self.get_function_history(req).map(HistoryResponse::Function),
+            DefinitionType::Object => self.get_object_history(req).map(HistoryResponse::Object)
         }.unwrap_or(HistoryResponse::Empty)
     }
 
@@ -149,10 +241,10 @@ impl SchemaManager {
         let limit = req.limit.unwrap_or(30);
         Ok(match req.mode {
             FetchMode::Compact => {
-                let result = self.get_definitions::<CompactTlConstructor>(&req.name, req.layer_id, DefinitionType::Object, limit).await?;
+                let result = self.get_definitions::<CompactTlConstructor>(req, DefinitionType::Object, limit).await?;
                 GetObjectResponse::CompactMode(result.hits.into_iter().map(|a| a.result).collect())
             }
-            FetchMode::Full => GetObjectResponse::FullMode(self.get_obj_full(Some(limit), &req.name, req.layer_id))
+            FetchMode::Full => GetObjectResponse::FullMode(self.get_obj_full(Some(limit), &req.name, req.family, req.layer_id, req.section))
         })
     }
 
@@ -160,17 +252,25 @@ impl SchemaManager {
         let limit = req.limit.unwrap_or(30);
         Ok(match req.mode {
             FetchMode::Compact => {
-                let result = self.get_definitions::<CompactTlDefinition>(&req.name, req.layer_id, DefinitionType::Function, limit).await?;
+                let result = self.get_definitions::<CompactTlDefinition>(req, DefinitionType::Function, limit).await?;
                 GetFuncResponse::CompactMode(result.hits.into_iter().map(|a| a.result).collect())
             }
-            FetchMode::Full => GetFuncResponse::FullMode(self.get_func_full(Some(limit), &req.name, req.layer_id))
+            FetchMode::Full => GetFuncResponse::FullMode(self.get_func_full(Some(limit), &req.name, req.family, req.layer_id, req.section))
         })
     }
 
     pub async fn search(&self, req: &SearchLayerRequest) -> eyre::Result<SearchResponse> {
-        let filter = if let Some(layer_id) = req.layer_id {
-            format!("layer_id = {layer_id}")
-        } else { String::default() };
+        let mut filter = vec![format!("family = {}", req.family)];
+        if let Some(layer_id) = req.layer_id {
+            filter.push(format!("layer_id = {layer_id}"));
+        }
+        if req.section != SchemaSection::All {
+            filter.push(format!("section = {}", req.section));
+        }
+        if let Some(dialect) = req.dialect {
+            filter.push(format!("dialect = {dialect}"));
+        }
+        let filter = filter.join(" AND ");
         let search_on = if req.filter.is_empty() { vec!["*"] } else { req.filter.iter().map(|s| s.as_str()).collect::<Vec<_>>() };
         let prefix = if let Some(prefix) = &req.highlight_prefix { prefix.as_str() } else { "" };
         let postfix = if let Some(postfix) = &req.highlight_postfix { postfix.as_str() } else { "" };
@@ -208,26 +308,153 @@ impl SchemaManager {
         Ok(self.meilisearch.get_task(&self.init_task_info).await?.is_success())
     }
 
-    pub fn get_layer(&self, layer_id: i32) -> Option<&TlSchema> {
-        self.schemas.iter().find(|s| s.layer_id == layer_id)
+    pub fn get_layer(&self, family: SchemaFamily, layer_id: i32, section: SchemaSection) -> Option<GetLayerResponse<'_>> {
+        let schema = self.schema(family, layer_id)?;
+        Some(GetLayerResponse {
+            family,
+            layer_id: schema.layer_id,
+            dialect: schema.dialect,
+            release_date: schema.release_date,
+            metadata: &schema.metadata,
+            api: (section != SchemaSection::Mtproto).then_some(&schema.api),
+            mtproto: (section != SchemaSection::Api).then_some(&schema.mtproto),
+            diagnostics: &schema.diagnostics,
+        })
+    }
+
+    pub fn get_layer_text(&self, family: SchemaFamily, layer_id: i32, section: SchemaSection) -> Option<String> {
+        let schema = self.schema(family, layer_id)?;
+        Some(printer::schema_text(schema, section))
+    }
+
+    pub fn lint(&self, family: SchemaFamily, layer_id: i32, section: SchemaSection, config: &LintConfig) -> Option<LintReport> {
+        let schema = self.schema(family, layer_id)?;
+        Some(lint::lint(schema, section, config))
+    }
+
+    /// what changed between two layers of the family, `None` if one of them isn't loaded
+    pub fn diff(&self, family: SchemaFamily, from: i32, to: i32, section: SchemaSection, breaking_only: bool) -> Option<TlLayerDiff<'_>> {
+        let diff = layer_diff::diff(self.schema(family, from)?, self.schema(family, to)?, section);
+        Some(if breaking_only { diff.breaking() } else { diff })
+    }
+
+    /// release notes of a layer compared with the layer loaded before it
+    pub fn changelog(&self, family: SchemaFamily, layer_id: i32) -> Option<TlChangelog> {
+        let schema = self.schema(family, layer_id)?;
+        let previous = self.layer_ids(family, schema.dialect).into_iter().filter(|id| *id < layer_id).max()?;
+        Some(TlChangelog::new(&layer_diff::diff(self.schema(family, previous)?, schema, SchemaSection::All)))
+    }
+
+    pub fn graph(&self, family: SchemaFamily, layer_id: i32, section: SchemaSection) -> Option<&TlGraph> {
+        let i = self.schemas.iter().position(|s| s.family == family && s.layer_id == layer_id)?;
+        Some(self.graphs[i][section as usize].get_or_init(|| TlGraph::new(&self.schemas[i], section)))
+    }
+
+    pub fn encode(&self, family: SchemaFamily, layer_id: i32, req: &EncodeRequest) -> Option<eyre::Result<Vec<u8>>> {
+        let schema = self.schema(family, layer_id)?;
+        Some(payload_type(&req._type).and_then(|t| TlCodec::new(schema).encode(&req.value, t.as_ref())))
+    }
+
+    /// `None` if the layer doesn't exist
+    pub fn decode(&self, family: SchemaFamily, layer_id: i32, req: &DecodeRequest) -> Option<eyre::Result<serde_json::Value>> {
+        let schema = self.schema(family, layer_id)?;
+        let decode = || TlCodec::new(schema).decode(&codec::parse_payload(&req.payload)?, payload_type(&req._type)?.as_ref());
+        Some(decode())
     }
 
-    pub fn get_compact_layer(&self, layer_id: i32) -> Vec<&CompactTlDefinition> {
-        self.compact_definitions.iter().filter(|s| s.layer_id == layer_id).collect::<Vec<_>>()
+    /// decodes a payload under every layer that defines its leading id
+    pub fn inspect(&self, req: &InspectRequest) -> eyre::Result<PayloadReport> {
+        let bytes = codec::parse_payload(&req.payload)?;
+        let schemas = self.ids
+            .get(&inspector::leading_id(&bytes)?)
+            .into_iter()
+            .flatten()
+            .map(|e| e.schema)
+            .dedup()
+            .map(|i| &self.schemas[i])
+            .filter(|s| req.family.is_none_or(|f| s.family == f));
+        inspector::inspect(&bytes, schemas)
+    }
+
+    /// every definition with one of `ids`, the same definition in several layers is one hit
+    pub fn lookup_ids(&self, ids: &[u32], family: Option<SchemaFamily>) -> Vec<IdHit<'_>> {
+        let mut hits: Vec<IdHit> = vec![];
+        for entry in ids.iter().filter_map(|id| self.ids.get(id)).flatten() {
+            let schema = &self.schemas[entry.schema];
+            continue_if!(family.is_some_and(|f| f != schema.family));
+            let hit = match &entry.definition {
+                IdDefinition::Constructor { tl_type, constructor } => IdHit {
+                    id: &constructor.id,
+                    name: &constructor.name,
+                    family: schema.family,
+                    section: entry.section,
+                    definition_type: DefinitionType::Object,
+                    _type: Some(tl_type),
+                    return_type: None,
+                    tl: printer::constructor_line(constructor, tl_type),
+                    layers: vec![],
+                },
+                IdDefinition::Function(function) => IdHit {
+                    id: &function.id,
+                    name: &function.name,
+                    family: schema.family,
+                    section: entry.section,
+                    definition_type: DefinitionType::Function,
+                    _type: None,
+                    return_type: Some(&function.return_type),
+                    tl: printer::function_line(function),
+                    layers: vec![],
+                },
+            };
+            let index = match hits.iter().position(|h| h.family == hit.family && h.section == hit.section && h.tl == hit.tl) {
+                Some(i) => i,
+                None => {
+                    hits.push(hit);
+                    hits.len() - 1
+                }
+            };
+            hits[index].layers.push(schema.layer_id);
+        }
+        hits
+    }
+
+    pub fn get_compact_layer(&self, family: SchemaFamily, layer_id: i32, section: SchemaSection) -> Vec<&CompactTlDefinition> {
+        self.compact_definitions
+            .iter()
+            .filter(|s| s.family == family && s.layer_id == layer_id && (section == SchemaSection::All || s.section == section))
+            .collect::<Vec<_>>()
     }
 
-    pub fn release_dates(&self) -> Vec<LayerReleaseDate> {
+    pub fn get_flag_layout<'a>(&'a self, family: SchemaFamily, layer_id: i32, name: &'a str, section: SchemaSection) -> Option<FlagLayoutResponse<'a>> {
+        let schema = self.schema(family, layer_id)?;
+        let (definition_type, fields) = match schema.constructor(name, section) {
+            Some(ctor) => (DefinitionType::Object, &ctor.flag_fields),
+            None => (DefinitionType::Function, &schema.function(name, section)?.flag_fields),
+        };
+        Some(FlagLayoutResponse {
+            layer_id,
+            name,
+            definition_type,
+            fields: fields.iter().map(FlagFieldLayout::from).collect(),
+        })
+    }
+
+    pub fn release_dates(&self, family: SchemaFamily) -> Vec<LayerReleaseDate> {
         self.schemas
             .iter()
-            .map(|s| LayerReleaseDate { release_date: s.release_date.date(), layer_id: s.layer_id })
+            .filter(|s| s.family == family)
+            .map(|s| LayerReleaseDate { family, release_date: s.release_date.date(), layer_id: s.layer_id })
             .collect()
     }
 
-    async fn get_definitions<T: 'static + DeserializeOwned + Send + Sync>(&self, name: &str, layer_id: Option<u32>, d: DefinitionType, limit: usize) -> eyre::Result<SearchResults<T>> {
-        let mut filter = vec![format!("name={name}"), format!("definition_type={d}")];
-        if let Some(id) = layer_id {
+    async fn get_definitions<T: 'static + DeserializeOwned + Send + Sync>(&self, req: &GetByNameRequest, d: DefinitionType, limit: usize) -> eyre::Result<SearchResults<T>> {
+        let mut filter = vec![format!("name={}", req.name), format!("definition_type={d}"), format!("family={}", req.family)];
+        if let Some(id) = req.layer_id {
             filter.push(format!("layer_id={id}"));
         }
+        if req.section != SchemaSection::All {
+            filter.push(format!("section={}", req.section));
+        }
         let filter = filter.iter().map(|s| s.as_str()).collect::<Vec<_>>();
         Ok(self.meilisearch
             .index("schema")
@@ -237,16 +464,16 @@ impl SchemaManager {
             .execute::<T>().await?)
     }
 
-    fn get_func_full(&self, limit: Option<usize>, name: &str, layer_id: Option<u32>) -> Vec<GetFunction> {
-        let layers_to_iter = self.filter_schema_by_id(layer_id.map(|f| f as i32));
+    fn get_func_full(&self, limit: Option<usize>, name: &str, family: SchemaFamily, layer_id: Option<u32>, section: SchemaSection) -> Vec<GetFunction> {
+        let layers_to_iter = self.filter_schema_by_id(family, layer_id.map(|f| f as i32));
 
 
         let r = layers_to_iter.iter()
-            .flat_map(|layer| layer.functions.values()
-                .flat_map(|functions| functions
+            .flat_map(|layer| layer.functions(section)
+                .flat_map(|(_, functions)| functions
                     .iter()
                     .filter(|f| f.name.eq(name))
-                    .map(|f| GetFunction { function: f, layer_id: layer.layer_id as _ })
+                    .map(|f| GetFunction { function: f, layer_id: layer.layer_id as _, tl: printer::function_line(f) })
                     .collect::<Vec<_>>())
                 .collect::<Vec<_>>());
 
@@ -257,15 +484,15 @@ impl SchemaManager {
         }
     }
 
-    fn get_obj_full(&self, limit: Option<usize>, name: &str, layer_id: Option<u32>) -> Vec<GetObject> {
-        let layers_to_iter = self.filter_schema_by_id(layer_id.map(|f| f as i32));
+    fn get_obj_full(&self, limit: Option<usize>, name: &str, family: SchemaFamily, layer_id: Option<u32>, section: SchemaSection) -> Vec<GetObject> {
+        let layers_to_iter = self.filter_schema_by_id(family, layer_id.map(|f| f as i32));
 
         let r = layers_to_iter.iter()
-            .flat_map(|layer| layer.objects.iter()
+            .flat_map(|layer| layer.objects(section)
                 .flat_map(|tl_type| tl_type.constructors
                     .iter()
                     .filter(|f| f.name.eq(name))
-                    .map(|ctor| (&tl_type.name, GetObject { obj: ctor, layer_id: layer.layer_id as _, usages: vec![] }))
+                    .map(|ctor| (&tl_type.name, GetObject { obj: ctor, layer_id: layer.layer_id as _, usages: vec![], tl: printer::constructor_line(ctor, &tl_type.name) }))
                     .collect::<Vec<_>>()
                 )
                 .collect::<Vec<_>>());
@@ -281,34 +508,21 @@ impl SchemaManager {
             let Some(related_layer) = layers_to_iter.iter().find(|l| l.layer_id == obj.layer_id as i32) else {
                 continue;
             };
-            for funcs in related_layer.functions.values() {
+            for (_, funcs) in related_layer.functions(section) {
                 for func in funcs {
-                    if let Some(inner) = &func.inner_return_type {
-                        if inner.eq(*ns) {
-                            obj.usages.push(ObjectUsage::ViaNamespace { tl_function: func, is_inner: true });
-                        } else if inner.eq(&obj.obj.name) {
-                            obj.usages.push(ObjectUsage::ReturnType { tl_function: func, is_inner: true });
-                        }
-                    }
-                    if func.return_type.eq(*ns) {
-                        obj.usages.push(ObjectUsage::ViaNamespace { tl_function: func, is_inner: false });
-                    } else if func.return_type.eq(&obj.obj.name) {
-                        obj.usages.push(ObjectUsage::ReturnType { tl_function: func, is_inner: false });
+                    let is_inner = func.return_type.is_vector();
+                    match func.return_type.base_name() {
+                        Some(t) if t.eq(ns.as_str()) => obj.usages.push(ObjectUsage::ViaNamespace { tl_function: func, is_inner }),
+                        Some(t) if t.eq(obj.obj.name.as_str()) => obj.usages.push(ObjectUsage::ReturnType { tl_function: func, is_inner }),
+                        _ => {}
                     }
 
-
                     for param in &func.parameters {
-                        if let Some(inner) = &param.inner_type {
-                            if inner.eq(*ns) {
-                                obj.usages.push(ObjectUsage::ViaNamespace { tl_function: func, is_inner: true });
-                            } else if inner.eq(&obj.obj.name) {
-                                obj.usages.push(ObjectUsage::Param { tl_function: func, is_inner: true });
-                            }
-                        }
-                        if param._type.eq(*ns) {
-                            obj.usages.push(ObjectUsage::ViaNamespace { tl_function: func, is_inner: false });
-                        } else if param._type.eq(&obj.obj.name) {
-                            obj.usages.push(ObjectUsage::Param { tl_function: func, is_inner: false });
+                        let is_inner = param._type.is_vector();
+                        match param._type.base_name() {
+                            Some(t) if t.eq(ns.as_str()) => obj.usages.push(ObjectUsage::ViaNamespace { tl_function: func, is_inner }),
+                            Some(t) if t.eq(obj.obj.name.as_str()) => obj.usages.push(ObjectUsage::Param { tl_function: func, is_inner }),
+                            _ => {}
                         }
                     }
                 }
@@ -318,19 +532,29 @@ impl SchemaManager {
         objects.into_iter().map(|(_, b)| b).collect()
     }
 
-    fn get_object_history(&self, name: &str) -> Option<ObjectHistoryResponse> {
-        let mut objects = self.get_obj_full(None, name, None);
+    fn get_object_history(&self, req: &HistoryRequest) -> Option<ObjectHistoryResponse> {
+        let layers = self.layer_ids(req.family, req.dialect);
+        let mut objects = self.get_obj_full(None, &req.name, req.family, None, req.section);
+        objects.retain(|o| layers.contains(&(o.layer_id as i32)));
         if objects.is_empty() {
             return None;
         }
         objects.sort_by(|f, f2| f2.layer_id.cmp(&f.layer_id));
+        self.follow_renames(&mut objects, req.family, &layers, |s| matcher::constructors(s, req.section), |o| (&o.obj.name, o.layer_id), |name| self.get_obj_full(None, name, req.family, None, req.section));
 
         let mut history = vec![ObjectHistory::AddedIn { layer_id: objects.last().unwrap().layer_id }];
         let iter = objects.iter().tuple_windows();
         for (a, b) in iter {
+            continue_if!(a.obj.same_definition(b.obj));
+            if a.obj.name != b.obj.name {
+                history.push(ObjectHistory::Renamed { layer_id: a.layer_id, from: &b.obj.name, to: &a.obj.name });
+            }
+            if a.obj.id != b.obj.id {
+                history.push(ObjectHistory::IdChanged { layer_id: a.layer_id, before: &b.obj.id, after: &a.obj.id });
+            }
             for param_b in &b.obj.parameters {
                 if !a.obj.parameters.iter().any(|f| f.name == param_b.name) {
-                    history.push(ObjectHistory::ParamDeleted { layer_id: a.layer_id, name: &param_b.name });
+                    history.push(ObjectHistory::ParamDeleted { layer_id: a.layer_id, name: &param_b.name, param_type: &param_b._type });
                 }
             }
             for param_a in &a.obj.parameters {
@@ -342,32 +566,49 @@ impl SchemaManager {
                     history.push(ObjectHistory::ParamAdded { layer_id: a.layer_id, param_type: &param_a._type, name: &param_a.name });
                 }
             }
+            if layer_diff::reordered(&b.obj.parameters, &a.obj.parameters) {
+                history.push(ObjectHistory::ParamsReordered { layer_id: a.layer_id });
+            }
         }
-        let latest_layer = self.schemas.last().unwrap().layer_id;
+        let latest_layer = *layers.last().unwrap();
         let last_appeared_in = objects.first().cloned().unwrap();
         if last_appeared_in.layer_id as i32 != latest_layer {
             history.push(ObjectHistory::DeletedIn { layer_id: last_appeared_in.layer_id });
         }
+        let history = history.into_iter()
+            .map(Classify::classified)
+            .filter(|c| !req.breaking_only || c.impact.is_breaking())
+            .collect();
         Some(ObjectHistoryResponse { history, last_definition: last_appeared_in })
     }
 
-    fn get_function_history(&self, name: &str) -> Option<FunctionHistoryResponse> {
-        let mut functions = self.get_func_full(None, name, None);
+    fn get_function_history(&self, req: &HistoryRequest) -> Option<FunctionHistoryResponse> {
+        let layers = self.layer_ids(req.family, req.dialect);
+        let mut functions = self.get_func_full(None, &req.name, req.family, None, req.section);
+        functions.retain(|f| layers.contains(&(f.layer_id as i32)));
         if functions.is_empty() {
             return None;
         }
         functions.sort_by(|f, f2| f2.layer_id.cmp(&f.layer_id));
+        self.follow_renames(&mut functions, req.family, &layers, |s| matcher::functions(s, req.section), |o| (&o.function.name, o.layer_id), |name| self.get_func_full(None, name, req.family, None, req.section));
 
         let mut history = vec![FunctionHistory::AddedIn { layer_id: functions.last().unwrap().layer_id }];
 
         let iter = functions.iter().tuple_windows();
         for (a, b) in iter {
+            continue_if!(a.function.same_definition(b.function));
+            if a.function.name != b.function.name {
+                history.push(FunctionHistory::Renamed { layer_id: a.layer_id, from: &b.function.name, to: &a.function.name });
+            }
+            if a.function.id != b.function.id {
+                history.push(FunctionHistory::IdChanged { layer_id: a.layer_id, before: &b.function.id, after: &a.function.id });
+            }
             if a.function.return_type != b.function.return_type {
                 history.push(FunctionHistory::ReturnTypeChanged { layer_id: a.layer_id, before: &b.function.return_type, after: &a.function.return_type });
             }
             for param_b in &b.function.parameters {
                 if !a.function.parameters.iter().any(|f| f.name == param_b.name) {
-                    history.push(FunctionHistory::ParamDeleted { layer_id: a.layer_id, name: &param_b.name });
+                    history.push(FunctionHistory::ParamDeleted { layer_id: a.layer_id, name: &param_b.name, param_type: &param_b._type });
                 }
             }
             for param_a in &a.function.parameters {
@@ -379,55 +620,128 @@ impl SchemaManager {
                     history.push(FunctionHistory::ParamAdded { layer_id: a.layer_id, param_type: &param_a._type, name: &param_a.name });
                 }
             }
+            if layer_diff::reordered(&b.function.parameters, &a.function.parameters) {
+                history.push(FunctionHistory::ParamsReordered { layer_id: a.layer_id });
+            }
         }
-        let latest_layer = self.schemas.last().unwrap().layer_id;
+        let latest_layer = *layers.last().unwrap();
         let last_appeared_in = functions.first().cloned().unwrap();
         if last_appeared_in.layer_id as i32 != latest_layer {
             history.push(FunctionHistory::DeletedIn { layer_id: last_appeared_in.layer_id });
         }
+        let history = history.into_iter()
+            .map(Classify::classified)
+            .filter(|c| !req.breaking_only || c.impact.is_breaking())
+            .collect();
         Some(FunctionHistoryResponse { history, last_definition: last_appeared_in })
     }
 
-    fn filter_schema_by_id(&self, layer_id: Option<i32>) -> Vec<&TlSchema> {
+    /// extends `definitions`, sorted newest first, with the ones they were renamed from and to.
+    /// `find` looks a name up in every layer and `name` gives the name and layer of a definition
+    fn follow_renames<'a, D>(&'a self, definitions: &mut Vec<D>, family: SchemaFamily, layers: &[i32], candidates: impl Fn(&'a TlSchema) -> Vec<TlCandidate<'a>>, name: impl Fn(&D) -> (&TlIdent, u32), find: impl Fn(&str) -> Vec<D>) {
+        let mut seen = definitions.first().map(|d| name(d).0.to_string()).into_iter().collect::<HashSet<_>>();
+        let mut renamed = |definitions: &Vec<D>, older: bool| -> Option<(String, i32)> {
+            let d = if older { definitions.last()? } else { definitions.first()? };
+            let (current, layer_id) = name(d);
+            let i = layers.iter().position(|l| *l == layer_id as i32)?;
+            let j = if older { i.checked_sub(1)? } else { i + 1 };
+            let (before, after) = if older { (layers[j], layers[i]) } else { (layers[i], *layers.get(j)?) };
+            let pairs = matcher::renamed(&candidates(self.schema(family, before)?), &candidates(self.schema(family, after)?));
+            let other = pairs.into_iter().find_map(|(old, new)| if older { (new == current).then_some(old) } else { (old == current).then_some(new) })?;
+            seen.insert(other.to_string()).then(|| (other.to_string(), layers[j]))
+        };
+
+        while let Some((older, since)) = renamed(definitions, true) {
+            definitions.extend(find(&older).into_iter().filter(|d| layers.contains(&(name(d).1 as i32)) && name(d).1 as i32 <= since).sorted_by_key(|d| Reverse(name(d).1)));
+        }
+        while let Some((newer, since)) = renamed(definitions, false) {
+            let newer = find(&newer).into_iter().filter(|d| layers.contains(&(name(d).1 as i32)) && name(d).1 as i32 >= since).sorted_by_key(|d| Reverse(name(d).1));
+            definitions.splice(0..0, newer);
+        }
+    }
+
+    /// sorted ids of the layers of `family` parsed as `dialect`
+    fn layer_ids(&self, family: SchemaFamily, dialect: TlDialect) -> Vec<i32> {
+        self.schemas.iter().filter(|s| s.family == family && s.dialect == dialect).map(|s| s.layer_id).collect()
+    }
+
+    fn schema(&self, family: SchemaFamily, layer_id: i32) -> Option<&TlSchema> {
+        self.schemas.iter().find(|s| s.family == family && s.layer_id == layer_id)
+    }
+
+    fn filter_schema_by_id(&self, family: SchemaFamily, layer_id: Option<i32>) -> Vec<&TlSchema> {
         if let Some(layer_id) = layer_id {
-            self.schemas.iter().find(|s| s.layer_id == layer_id)
+            self.schema(family, layer_id)
                 .map(|s| vec![s])
                 .unwrap_or(vec![])
         } else {
-            self.schemas.iter().collect()
+            self.schemas.iter().filter(|s| s.family == family).collect()
+        }
+    }
+
+    fn index_ids(schemas: &[TlSchema]) -> HashMap<u32, Vec<IdEntry>> {
+        let mut index = HashMap::<u32, Vec<IdEntry>>::new();
+        for (i, schema) in schemas.iter().enumerate() {
+            for (section, s) in [(SchemaSection::Api, &schema.api), (SchemaSection::Mtproto, &schema.mtproto)] {
+                let constructors = s.objects.iter()
+                    .flat_map(|t| t.constructors.iter().map(|c| IdDefinition::Constructor { tl_type: t.name.clone(), constructor: c.clone() }));
+                let functions = s.functions.values().flatten().map(|f| IdDefinition::Function(f.clone()));
+                for definition in constructors.chain(functions) {
+                    let Some(id) = tl::constructor_id::parse_id(definition.id()) else { continue; };
+                    index.entry(id).or_default().push(IdEntry { schema: i, section, definition });
+                }
+            }
         }
+        index
     }
 
-    fn create_compact_definitions(schemas: &Vec<TlSchema>) -> Vec<CompactTlDefinition> {
+    /// search documents of every layer, in the same order as `schemas`
+    fn create_compact_definitions(schemas: &[TlSchema]) -> Vec<CompactTlDefinition> {
+        schemas.par_iter().flat_map_iter(Self::compact_definitions).collect()
+    }
+
+    fn compact_definitions(schema: &TlSchema) -> Vec<CompactTlDefinition> {
         let mut definitions = vec![];
-        for schema in schemas {
-            schema.functions.iter().for_each(|(ns, funcs)| {
+        for (section, tl_section) in [(SchemaSection::Api, &schema.api), (SchemaSection::Mtproto, &schema.mtproto)] {
+            tl_section.functions.iter().sorted_by_key(|(ns, _)| *ns).for_each(|(ns, funcs)| {
                 funcs.iter().for_each(|f| definitions.push(
                     CompactTlDefinition {
                         id: uuid::Uuid::new_v4(),
+                        family: schema.family,
                         layer_id: schema.layer_id,
                         name: f.name.to_owned(),
-                        return_type: Some(f.return_type.to_owned()),
+                        return_type: Some(f.return_type.ident()),
                         definition_id: f.id.to_owned(),
                         namespace: ns.to_owned(),
                         definition_type: DefinitionType::Function,
+                        section,
+                        description: f.description.to_owned(),
+                        dialect: schema.dialect,
                     }
                 ));
             });
-            schema.objects.iter().for_each(|tl_types| {
+            tl_section.objects.iter().for_each(|tl_types| {
                 tl_types.constructors.iter().for_each(|f| definitions.push(
                     CompactTlDefinition {
                         id: uuid::Uuid::new_v4(),
+                        family: schema.family,
                         layer_id: schema.layer_id,
                         name: f.name.to_owned(),
                         return_type: None,
                         definition_id: f.id.to_owned(),
                         namespace: tl_types.name.to_owned(),
                         definition_type: DefinitionType::Object,
+                        section,
+                        description: f.description.to_owned(),
+                        dialect: schema.dialect,
                     }
                 ));
             });
         }
         definitions
     }
-}
\ No newline at end of file
+}
+
+fn payload_type(t: &Option<String>) -> eyre::Result<Option<TlTypeRef>> {
+    t.as_deref().map(|t| t.parse().map_err(eyre::Report::msg)).transpose()
+}
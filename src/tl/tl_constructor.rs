@@ -1,11 +1,20 @@
 use serde::{Deserialize, Serialize};
+use crate::tl::tl_flag::TlFlagField;
 use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_ident::TlIdent;
 
 /// a tl object
 #[derive(Debug, Serialize,Deserialize)]
 pub struct TlConstructor {
-    pub id: String,
-    pub name: String,
-    pub namespace:Option<String>,
+    pub id: TlIdent,
+    /// crc32 id computed from the definition, differs from `id` only if the declared id is wrong
+    pub computed_id: TlIdent,
+    pub name: TlIdent,
+    pub namespace: Option<TlIdent>,
+    /// `{X:Type}` headers
+    pub type_params: Vec<TlIdent>,
     pub parameters: Vec<TlParameter>,
-}
\ No newline at end of file
+    /// `#` parameters and the bits used by the rest of the parameters
+    pub flag_fields: Vec<TlFlagField>,
+    pub description: Option<String>,
+}
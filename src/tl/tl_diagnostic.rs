@@ -0,0 +1,48 @@
+use std::fmt::Display;
+use serde::{Deserialize, Serialize};
+use crate::tl::tl_span::TlSpan;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// the definition was skipped or fixed up, the rest of the layer is usable
+    Warning,
+    /// the layer can't be used at all
+    Error,
+}
+
+/// a problem found while parsing a layer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TlDiagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    /// the source line the problem was found on
+    pub snippet: String,
+    pub message: String,
+}
+
+impl TlDiagnostic {
+    pub fn warning<M: Into<String>>(src: &str, span: TlSpan, message: M) -> Self {
+        Self::new(Severity::Warning, src, span, message.into())
+    }
+
+    pub fn error<M: Into<String>>(src: &str, span: TlSpan, message: M) -> Self {
+        Self::new(Severity::Error, src, span, message.into())
+    }
+
+    fn new(severity: Severity, src: &str, span: TlSpan, message: String) -> Self {
+        let snippet = src.lines().nth(span.line.saturating_sub(1)).unwrap_or_default().trim().to_owned();
+        Self { severity, line: span.line, column: span.column, snippet, message }
+    }
+}
+
+impl Display for TlDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity} at {}:{}: {} (`{}`)", self.line, self.column, self.message, self.snippet)
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_type_ref::TlTypeRef;
+use crate::tl::tl_ident::TlIdent;
+
+/// a `flags:#` parameter and the bits other parameters depend on
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TlFlagField {
+    pub name: TlIdent,
+    /// used bits, sorted
+    pub bits: Vec<TlFlagBit>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TlFlagBit {
+    pub bit: u8,
+    /// parameters that are only present when the bit is set, more than one means they always move together
+    pub parameters: Vec<TlIdent>,
+}
+
+impl TlFlagField {
+    /// bits below the highest used one that nothing depends on
+    pub fn unused_bits(&self) -> Vec<u8> {
+        let Some(highest) = self.bits.last().map(|b| b.bit) else {
+            return vec![];
+        };
+        (0..highest).filter(|bit| !self.bits.iter().any(|b| b.bit == *bit)).collect()
+    }
+
+    /// bits that control more than one parameter
+    pub fn groups(&self) -> Vec<&TlFlagBit> {
+        self.bits.iter().filter(|b| b.parameters.len() > 1).collect()
+    }
+}
+
+/// collects every `#` parameter and the `field.N?T` parameters that reference it
+pub fn flag_fields(parameters: &[TlParameter]) -> Vec<TlFlagField> {
+    let mut fields = parameters
+        .iter()
+        .filter(|p| p._type == TlTypeRef::Nat && !p.name.is_empty())
+        .map(|p| TlFlagField { name: p.name.to_owned(), bits: vec![] })
+        .collect::<Vec<_>>();
+
+    for param in parameters {
+        let Some((field, bit)) = param._type.flag() else {
+            continue;
+        };
+        let Some(field) = fields.iter_mut().find(|f| f.name == field) else {
+            continue;
+        };
+        match field.bits.iter_mut().find(|b| b.bit == bit) {
+            Some(b) => b.parameters.push(param.name.to_owned()),
+            None => field.bits.push(TlFlagBit { bit, parameters: vec![param.name.to_owned()] }),
+        }
+    }
+    for field in &mut fields {
+        field.bits.sort_by_key(|b| b.bit);
+    }
+    fields
+}
@@ -1,11 +1,20 @@
 use serde::{Deserialize, Serialize};
+use crate::tl::tl_flag::TlFlagField;
 use crate::tl::tl_parameter::TlParameter;
+use crate::tl::tl_type_ref::TlTypeRef;
+use crate::tl::tl_ident::TlIdent;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct TlFunction {
-    pub id: String,
-    pub name: String,
+    pub id: TlIdent,
+    /// crc32 id computed from the definition, differs from `id` only if the declared id is wrong
+    pub computed_id: TlIdent,
+    pub name: TlIdent,
+    /// `{X:Type}` headers
+    pub type_params: Vec<TlIdent>,
     pub parameters: Vec<TlParameter>,
-    pub return_type: String,
-    pub inner_return_type:Option<String>
-}
\ No newline at end of file
+    /// `#` parameters and the bits used by the rest of the parameters
+    pub flag_fields: Vec<TlFlagField>,
+    pub return_type: TlTypeRef,
+    pub description: Option<String>,
+}
@@ -0,0 +1,264 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Write};
+use serde::{Deserialize, Serialize};
+use crate::models::requests::SchemaSection;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_type_ref::TlTypeRef;
+use crate::tl::TlSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum TlNodeKind {
+    /// a boxed type, primitives like `int` are types too
+    Type,
+    Constructor,
+    Function,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TlNode {
+    pub kind: TlNodeKind,
+    pub name: TlIdent,
+}
+
+/// which way to follow the edges of a [`TlGraph`]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TlDirection {
+    /// what a node needs
+    #[default]
+    Dependencies,
+    /// what needs a node
+    Dependents,
+}
+
+impl Display for TlDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            TlDirection::Dependencies => "dependencies",
+            TlDirection::Dependents => "dependents",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// output of [`TlSubgraph::render`]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TlGraphFormat {
+    /// graphviz
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+/// nodes a walk over a [`TlGraph`] doesn't enter, the root is always kept
+#[derive(Debug, Default)]
+pub struct TlGraphFilter {
+    /// only constructors, functions and types of this namespace, `""` for the ones without a namespace
+    pub namespace: Option<String>,
+    /// leave out primitives like `int`, `string` and `Bool`
+    pub collapse_primitives: bool,
+}
+
+impl TlGraphFilter {
+    fn keeps(&self, node: &TlNode) -> bool {
+        if self.collapse_primitives && node.is_primitive() {
+            return false;
+        }
+        match &self.namespace {
+            Some(ns) => node.namespace() == ns,
+            None => true,
+        }
+    }
+}
+
+impl TlNode {
+    pub fn namespace(&self) -> &str {
+        self.name.split_once('.').map(|(ns, _)| ns).unwrap_or_default()
+    }
+
+    /// bare names that aren't constructors are only added as types, so a lowercase type is a primitive
+    pub fn is_primitive(&self) -> bool {
+        let last = self.name.rsplit('.').next().unwrap_or(&self.name);
+        self.kind == TlNodeKind::Type && (last == "Bool" || last.starts_with(|c: char| c.is_ascii_lowercase()))
+    }
+}
+
+/// who uses whom in a layer: types link to their constructors, constructors to the types of their parameters,
+/// functions to the types of their parameters and their return type
+pub struct TlGraph {
+    nodes: Vec<TlNode>,
+    index: HashMap<TlNode, usize>,
+    dependencies: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+}
+
+/// the nodes reachable from a root, edges always point from the node that depends to its dependency
+#[derive(Serialize, Debug)]
+pub struct TlSubgraph {
+    pub root: TlNode,
+    pub direction: TlDirection,
+    /// the root comes first, then the rest by depth
+    pub nodes: Vec<TlReachedNode>,
+    /// `(from, to)` indexes into `nodes`
+    pub edges: Vec<(usize, usize)>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TlReachedNode {
+    #[serde(flatten)]
+    pub node: TlNode,
+    /// number of edges from the root
+    pub depth: usize,
+}
+
+impl TlGraph {
+    pub fn new(schema: &TlSchema, section: SchemaSection) -> Self {
+        let mut graph = Self { nodes: vec![], index: HashMap::new(), dependencies: vec![], dependents: vec![] };
+        let constructors = schema.objects(section)
+            .flat_map(|t| &t.constructors)
+            .map(|c| c.name.as_str())
+            .collect::<HashSet<_>>();
+
+        for tl_type in schema.objects(section) {
+            let ty = graph.node(TlNodeKind::Type, &tl_type.name);
+            for c in &tl_type.constructors {
+                let constructor = graph.node(TlNodeKind::Constructor, &c.name);
+                graph.link(ty, constructor);
+                for p in &c.parameters {
+                    graph.link_type_ref(constructor, &p._type, &c.type_params, &constructors);
+                }
+            }
+        }
+        for f in schema.functions(section).flat_map(|(_, f)| f) {
+            let function = graph.node(TlNodeKind::Function, &f.name);
+            for p in &f.parameters {
+                graph.link_type_ref(function, &p._type, &f.type_params, &constructors);
+            }
+            graph.link_type_ref(function, &f.return_type, &f.type_params, &constructors);
+        }
+        graph
+    }
+
+    /// a node by name, a missing `kind` tries types, then constructors, then functions
+    pub fn find(&self, name: &str, kind: Option<TlNodeKind>) -> Option<usize> {
+        let kinds = match kind {
+            Some(kind) => vec![kind],
+            None => vec![TlNodeKind::Type, TlNodeKind::Constructor, TlNodeKind::Function],
+        };
+        kinds.into_iter().find_map(|kind| self.index.get(&TlNode { kind, name: TlIdent::new(name) }).copied())
+    }
+
+    /// every node reachable from `root` within `depth` edges, all of them if `depth` is missing
+    pub fn closure(&self, root: usize, direction: TlDirection, depth: Option<usize>, filter: &TlGraphFilter) -> TlSubgraph {
+        let edges = match direction {
+            TlDirection::Dependencies => &self.dependencies,
+            TlDirection::Dependents => &self.dependents,
+        };
+        let mut reached = HashMap::from([(root, 0)]);
+        let mut order = vec![root];
+        let mut walked = vec![];
+        let mut queue = VecDeque::from([root]);
+        while let Some(node) = queue.pop_front() {
+            let d = reached[&node];
+            if depth.is_some_and(|max| d >= max) {
+                continue;
+            }
+            for &next in edges[node].iter().filter(|n| filter.keeps(&self.nodes[**n])) {
+                walked.push((node, next));
+                if let Entry::Vacant(e) = reached.entry(next) {
+                    e.insert(d + 1);
+                    order.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let position = order.iter().enumerate().map(|(i, n)| (*n, i)).collect::<HashMap<_, _>>();
+        let edges = walked.into_iter()
+            .map(|(a, b)| (position[&a], position[&b]))
+            .map(|(a, b)| if direction == TlDirection::Dependencies { (a, b) } else { (b, a) })
+            .collect();
+        let nodes = order.into_iter()
+            .map(|n| TlReachedNode { node: self.nodes[n].clone(), depth: reached[&n] })
+            .collect();
+        TlSubgraph { root: self.nodes[root].clone(), direction, nodes, edges }
+    }
+
+    fn node(&mut self, kind: TlNodeKind, name: &TlIdent) -> usize {
+        let node = TlNode { kind, name: name.clone() };
+        if let Some(i) = self.index.get(&node) {
+            return *i;
+        }
+        self.nodes.push(node.clone());
+        self.dependencies.push(vec![]);
+        self.dependents.push(vec![]);
+        self.index.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn link(&mut self, from: usize, to: usize) {
+        if from != to && !self.dependencies[from].contains(&to) {
+            self.dependencies[from].push(to);
+            self.dependents[to].push(from);
+        }
+    }
+
+    /// links to the innermost type of `t`, a bare name of a constructor links to the constructor itself.
+    /// `#` and type variables like `!X` don't link to anything
+    fn link_type_ref(&mut self, from: usize, t: &TlTypeRef, type_params: &[TlIdent], constructors: &HashSet<&str>) {
+        let (kind, name) = match t.innermost() {
+            TlTypeRef::Bare { name } if constructors.contains(name.as_str()) => (TlNodeKind::Constructor, name),
+            TlTypeRef::Bare { name } | TlTypeRef::Boxed { name } if !type_params.contains(name) => (TlNodeKind::Type, name),
+            _ => return,
+        };
+        let to = self.node(kind, name);
+        self.link(from, to);
+    }
+}
+
+impl TlSubgraph {
+    pub fn render(&self, format: TlGraphFormat) -> String {
+        match format {
+            TlGraphFormat::Dot => self.to_dot(),
+            TlGraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    /// types are ellipses, constructors boxes and functions rounded boxes, the root is bold
+    pub fn to_dot(&self) -> String {
+        let mut out = format!("digraph \"{}\" {{\n    rankdir=LR;\n    node [fontname=\"monospace\"];\n", self.root.name);
+        for (i, n) in self.nodes.iter().enumerate() {
+            let shape = match n.node.kind {
+                TlNodeKind::Type => "shape=ellipse",
+                TlNodeKind::Constructor => "shape=box",
+                TlNodeKind::Function => "shape=box, style=rounded",
+            };
+            let root = if i == 0 { ", penwidth=2" } else { "" };
+            let _ = writeln!(out, "    n{i} [label=\"{}\", {shape}{root}];", n.node.name);
+        }
+        for (a, b) in &self.edges {
+            let _ = writeln!(out, "    n{a} -> n{b};");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// types are stadiums, constructors boxes and functions subroutines
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart LR\n");
+        for (i, n) in self.nodes.iter().enumerate() {
+            let (open, close) = match n.node.kind {
+                TlNodeKind::Type => ("([", "])"),
+                TlNodeKind::Constructor => ("[", "]"),
+                TlNodeKind::Function => ("[[", "]]"),
+            };
+            let _ = writeln!(out, "    n{i}{open}\"{}\"{close}", n.node.name);
+        }
+        for (a, b) in &self.edges {
+            let _ = writeln!(out, "    n{a} --> n{b}");
+        }
+        out
+    }
+}
@@ -0,0 +1,129 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// the interner is split so layers parsed in parallel rarely wait on the same lock
+const SHARDS: usize = 16;
+
+lazy_static! {
+    /// every identifier ever parsed, layers share almost all of their names so each one is stored once
+    static ref IDENTS: Vec<Mutex<HashSet<Arc<str>>>> = (0..SHARDS).map(|_| Mutex::default()).collect();
+}
+
+fn shard(s: &str) -> &'static Mutex<HashSet<Arc<str>>> {
+    let hash = s.bytes().fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+    &IDENTS[hash % SHARDS]
+}
+
+/// an interned name, id or namespace. cloning it is a reference count increment
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TlIdent(Arc<str>);
+
+impl Default for TlIdent {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl TlIdent {
+    pub fn new(s: &str) -> Self {
+        let mut idents = shard(s).lock().unwrap();
+        match idents.get(s) {
+            Some(ident) => Self(Arc::clone(ident)),
+            None => {
+                let ident: Arc<str> = Arc::from(s);
+                idents.insert(Arc::clone(&ident));
+                Self(ident)
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// `(identifiers, bytes)` held by the interner
+pub fn interned() -> (usize, usize) {
+    IDENTS.iter()
+        .map(|shard| shard.lock().unwrap())
+        .fold((0, 0), |(n, bytes), idents| (n + idents.len(), bytes + idents.iter().map(|i| i.len()).sum::<usize>()))
+}
+
+impl Deref for TlIdent {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Borrow<str> for TlIdent {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for TlIdent {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for TlIdent {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for TlIdent {
+    fn from(value: String) -> Self {
+        Self::new(&value)
+    }
+}
+
+impl PartialEq<str> for TlIdent {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for TlIdent {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for TlIdent {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl Display for TlIdent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::fmt::Debug for TlIdent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for TlIdent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for TlIdent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        Ok(Self::new(&s))
+    }
+}
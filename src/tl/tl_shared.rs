@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use lazy_static::lazy_static;
+use serde::{Serialize, Serializer};
+use crate::tl::parser::TlDeclaration;
+use crate::tl::printer;
+use crate::tl::tl_constructor::TlConstructor;
+use crate::tl::tl_function::TlFunction;
+use crate::tl::tl_span::TlSpan;
+use crate::tl::TlSchema;
+
+lazy_static! {
+    /// constructors of every parsed layer, most of them don't change between layers so each one is stored once.
+    /// nothing is ever removed, the layers are parsed once at startup and live as long as the process
+    static ref CONSTRUCTORS: Mutex<DefinitionStore<TlConstructor>> = Mutex::new(DefinitionStore::default());
+    static ref FUNCTIONS: Mutex<DefinitionStore<TlFunction>> = Mutex::new(DefinitionStore::default());
+}
+
+/// a definition that can live in a [`DefinitionStore`]
+pub trait SharedDefinition: Sized + 'static {
+    fn store() -> &'static Mutex<DefinitionStore<Self>>;
+}
+
+impl SharedDefinition for TlConstructor {
+    fn store() -> &'static Mutex<DefinitionStore<Self>> {
+        &CONSTRUCTORS
+    }
+}
+
+impl SharedDefinition for TlFunction {
+    fn store() -> &'static Mutex<DefinitionStore<Self>> {
+        &FUNCTIONS
+    }
+}
+
+/// definitions keyed by their canonical text, documentation and layout, layers that contain the same definition share one copy
+pub struct DefinitionStore<T> {
+    definitions: HashMap<String, Arc<T>>,
+}
+
+impl<T> Default for DefinitionStore<T> {
+    fn default() -> Self {
+        Self { definitions: HashMap::new() }
+    }
+}
+
+/// the stored copies of `declarations`, `build` only runs for definitions that weren't seen before.
+/// parameter spans of a stored copy are relative to the definition, the layout is part of the key
+/// so every layer that gets a copy has its parameters at the same offsets.
+/// the store is locked once to look the keys up and once to add the new copies, `build` runs without it.
+/// when two layers build the same definition at once the copy added first is kept
+pub fn share<T: SharedDefinition>(layer_id: i32, declarations: Vec<TlDeclaration>, build: impl Fn(TlDeclaration) -> T) -> Vec<TlShared<T>> {
+    let keyed = declarations.into_iter().map(|d| (definition_key(&d), d)).collect::<Vec<_>>();
+    let found = {
+        let store = T::store().lock().unwrap();
+        keyed.iter().map(|(key, _)| store.definitions.get(key).cloned()).collect::<Vec<_>>()
+    };
+
+    let mut built = vec![];
+    let mut shared = keyed.into_iter()
+        .zip(found)
+        .enumerate()
+        .map(|(i, ((key, mut d), found))| {
+            let span = d.span;
+            let definition = found.unwrap_or_else(|| {
+                for p in &mut d.parameters {
+                    p.span = p.span.relative_to(span);
+                }
+                built.push((i, key));
+                Arc::new(build(d))
+            });
+            TlShared { definition, span, unchanged_since: layer_id }
+        })
+        .collect::<Vec<_>>();
+
+    if !built.is_empty() {
+        let mut store = T::store().lock().unwrap();
+        for (i, key) in built {
+            let stored = store.definitions.entry(key).or_insert_with(|| Arc::clone(&shared[i].definition));
+            shared[i].definition = Arc::clone(stored);
+        }
+    }
+    shared
+}
+
+/// the text a definition is stored under: its printed line, everything the doc comment added to it
+/// and where its parameters are, two layers only share a definition they lay out the same way
+fn definition_key(d: &TlDeclaration) -> String {
+    let mut key = printer::declaration_line(d);
+    for doc in std::iter::once(&d.description).chain(d.parameters.iter().map(|p| &p.description)) {
+        key.push('\n');
+        key.push_str(doc.as_deref().unwrap_or_default());
+    }
+    for p in &d.parameters {
+        let span = p.span.relative_to(d.span);
+        let _ = write!(key, "\n{} {} {} {}", span.start, span.end, span.line, span.column);
+    }
+    key
+}
+
+/// a definition as it appears in one layer, the definition itself is shared by every layer that contains it
+#[derive(Debug)]
+pub struct TlShared<T> {
+    definition: Arc<T>,
+    /// position in this layer
+    pub span: TlSpan,
+    /// first layer of the consecutive layers, up to this one, that contain the definition unchanged
+    pub unchanged_since: i32,
+}
+
+impl<T> TlShared<T> {
+    /// whether both are the same stored copy, no matter which layers they belong to
+    pub fn same_definition(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.definition, &other.definition)
+    }
+
+    /// identifies the stored copy, it doesn't depend on the order layers were parsed in
+    fn key(&self) -> *const T {
+        Arc::as_ptr(&self.definition)
+    }
+}
+
+//a derived clone would need `T: Clone`, only the `Arc` is cloned
+impl<T> Clone for TlShared<T> {
+    fn clone(&self) -> Self {
+        Self { definition: Arc::clone(&self.definition), span: self.span, unchanged_since: self.unchanged_since }
+    }
+}
+
+impl<T> Deref for TlShared<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.definition
+    }
+}
+
+impl<T: Serialize> Serialize for TlShared<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Flat<'a, T> {
+            #[serde(flatten)]
+            definition: &'a T,
+            span: TlSpan,
+            unchanged_since: i32,
+        }
+        Flat { definition: &*self.definition, span: self.span, unchanged_since: self.unchanged_since }.serialize(serializer)
+    }
+}
+
+/// `(constructors, functions)` held by the stores
+pub fn stored() -> (usize, usize) {
+    (CONSTRUCTORS.lock().unwrap().definitions.len(), FUNCTIONS.lock().unwrap().definitions.len())
+}
+
+/// fills [`TlShared::unchanged_since`] by comparing every layer with the one before it, `schemas` are sorted by family and layer
+pub fn track_unchanged(schemas: &mut [TlSchema]) {
+    let mut family = None;
+    let (mut prev_constructors, mut prev_functions) = (HashMap::new(), HashMap::new());
+    for schema in schemas {
+        if family != Some(schema.family) {
+            family = Some(schema.family);
+            prev_constructors.clear();
+            prev_functions.clear();
+        }
+        let (mut constructors, mut functions) = (HashMap::new(), HashMap::new());
+        for section in [&mut schema.api, &mut schema.mtproto] {
+            let ctors = section.objects.iter_mut().flat_map(|t| &mut t.constructors);
+            link(ctors, &prev_constructors, &mut constructors, schema.layer_id);
+            link(section.functions.values_mut().flatten(), &prev_functions, &mut functions, schema.layer_id);
+        }
+        (prev_constructors, prev_functions) = (constructors, functions);
+    }
+}
+
+fn link<'a, T: 'a>(definitions: impl Iterator<Item=&'a mut TlShared<T>>, previous: &HashMap<*const T, i32>, current: &mut HashMap<*const T, i32>, layer_id: i32) {
+    for d in definitions {
+        d.unchanged_since = previous.get(&d.key()).copied().unwrap_or(layer_id);
+        current.insert(d.key(), d.unchanged_since);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::requests::{SchemaFamily, SchemaSection};
+    use crate::models::tl_layer::TlLayer;
+    use crate::tl::{self, TlSchema};
+
+    fn parse(layer_id: i32, src: &str) -> TlSchema {
+        tl::parse_schema(TlLayer { family: SchemaFamily::Custom, layer_id, layer: src.to_owned(), release_date: Default::default() }).unwrap()
+    }
+
+    fn constructor<'a>(schema: &'a TlSchema, name: &str) -> &'a tl::tl_shared::TlShared<tl::tl_constructor::TlConstructor> {
+        schema.objects(SchemaSection::All).flat_map(|t| &t.constructors).find(|c| c.name.as_str() == name).unwrap()
+    }
+
+    /// `name:type` of every parameter of a constructor, read from the layer text at its span
+    fn parameter_text<'a>(src: &'a str, schema: &TlSchema, name: &str) -> Vec<&'a str> {
+        let c = constructor(schema, name);
+        c.parameters.iter().map(|p| p.span.within(c.span)).map(|s| &src[s.start..s.end]).collect()
+    }
+
+    #[test]
+    fn layers_only_share_definitions_laid_out_the_same_way() {
+        let a = "sharedPoint#1b2e3f4a x:int y:int = SharedPoint;\nsharedDocumented#2b2e3f4a x:int = SharedPoint;";
+        let b = "sharedDocumented#2b2e3f4a x:int = SharedPoint;\nsharedPoint#1b2e3f4a x:int y:int = SharedPoint;";
+        let c = "sharedPoint#1b2e3f4a   x:int\n    y:int = SharedPoint;\n//@description a point\nsharedDocumented#2b2e3f4a x:int = SharedPoint;";
+        let (la, lb, lc) = (parse(1, a), parse(2, b), parse(3, c));
+        assert!(constructor(&la, "sharedPoint").same_definition(constructor(&lb, "sharedPoint")));
+        assert!(!constructor(&la, "sharedPoint").same_definition(constructor(&lc, "sharedPoint")));
+        assert!(!constructor(&la, "sharedDocumented").same_definition(constructor(&lc, "sharedDocumented")));
+        assert_eq!(parameter_text(b, &lb, "sharedPoint"), ["x:int", "y:int"]);
+        assert_eq!(parameter_text(c, &lc, "sharedPoint"), ["x:int", "y:int"]);
+        let y = constructor(&lc, "sharedPoint").parameters[1].span.within(constructor(&lc, "sharedPoint").span);
+        assert_eq!((y.line, y.column), (2, 5));
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// position of a definition (or a part of it) inside the layer text.
+/// `start`/`end` are byte offsets, `line`/`column` are 1-based and point at `start`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+pub struct TlSpan {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TlSpan {
+    /// a span that covers both `self` and `other`
+    pub fn to(self, other: TlSpan) -> TlSpan {
+        TlSpan { start: self.start, end: other.end, line: self.line, column: self.column }
+    }
+
+    /// `self` as an offset from `base`, which contains it. `line` becomes 0 on the first line of `base`
+    /// and `column` is counted from `base.column` there
+    pub fn relative_to(self, base: TlSpan) -> TlSpan {
+        let line = self.line - base.line;
+        let column = if line == 0 { self.column - base.column } else { self.column };
+        TlSpan { start: self.start - base.start, end: self.end - base.start, line, column }
+    }
+
+    /// the absolute position of a span made by [`TlSpan::relative_to`]
+    pub fn within(self, base: TlSpan) -> TlSpan {
+        let column = if self.line == 0 { self.column + base.column } else { self.column };
+        TlSpan { start: self.start + base.start, end: self.end + base.start, line: self.line + base.line, column }
+    }
+}
@@ -1,12 +1,51 @@
 use serde::{Deserialize, Serialize};
 use crate::tl::tl_constructor::TlConstructor;
+use crate::tl::tl_type_ref::TlTypeRef;
+use crate::tl::tl_ident::TlIdent;
+use crate::tl::tl_shared::TlShared;
 
 
 /// an abstract object, it doesn't exist really, it is there to categorize
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
 pub struct TlType {
-    pub name: String,
-    pub constructors: Vec<TlConstructor>,
-    //todo
-    //pub common_parameters: Vec<TlParameter>,
-}
\ No newline at end of file
+    pub name: TlIdent,
+    /// from `//@class` documentation
+    pub description: Option<String>,
+    pub constructors: Vec<TlShared<TlConstructor>>,
+    /// parameters shared by more than one constructor, the ones every constructor carries come first
+    pub common_parameters: Vec<TlCommonParameter>,
+}
+
+/// a parameter (same name and type) carried by several constructors of a type
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct TlCommonParameter {
+    pub name: TlIdent,
+    pub _type: TlTypeRef,
+    /// number of constructors that carry it
+    pub count: usize,
+    /// whether every constructor carries it
+    pub in_all: bool,
+}
+
+impl TlType {
+    pub fn new(name: TlIdent, description: Option<String>, constructors: Vec<TlShared<TlConstructor>>) -> Self {
+        let common_parameters = common_parameters(&constructors);
+        Self { name, description, constructors, common_parameters }
+    }
+}
+
+fn common_parameters(constructors: &[TlShared<TlConstructor>]) -> Vec<TlCommonParameter> {
+    let mut params: Vec<TlCommonParameter> = vec![];
+    for param in constructors.iter().flat_map(|c| &c.parameters).filter(|p| !p.name.is_empty()) {
+        match params.iter_mut().find(|p| p.name == param.name && p._type == param._type) {
+            Some(p) => p.count += 1,
+            None => params.push(TlCommonParameter { name: param.name.to_owned(), _type: param._type.clone(), count: 1, in_all: false }),
+        }
+    }
+    for p in &mut params {
+        p.in_all = p.count == constructors.len();
+    }
+    params.retain(|p| p.in_all || p.count > 1);
+    params.sort_by_key(|p| (!p.in_all, std::cmp::Reverse(p.count)));
+    params
+}
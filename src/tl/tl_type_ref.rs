@@ -0,0 +1,112 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::tl::parser;
+use crate::tl::tl_ident::TlIdent;
+
+/// type of a parameter or the result of a definition
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TlTypeRef {
+    /// `int`, `string`, or `%Message` (stored as `Message`)
+    Bare { name: TlIdent },
+    /// `User`, `messages.Messages`, or a `{X:Type}` variable used as a result
+    Boxed { name: TlIdent },
+    /// `Vector<T>`, `vector<T>` when bare
+    Vector { bare: bool, inner: Box<TlTypeRef> },
+    /// `!X`
+    Generic { name: TlIdent },
+    /// `flags.N?T`, the value is only present if bit `bit` of `field` is set
+    Flag { field: TlIdent, bit: u8, inner: Box<TlTypeRef> },
+    /// `#`, a flags field
+    Nat,
+}
+
+impl TlTypeRef {
+    /// `int` -> bare, `Int`/`ns.Int` -> boxed
+    pub fn named(name: TlIdent) -> Self {
+        let last = name.rsplit('.').next().unwrap_or(&name);
+        if last.starts_with(|c: char| c.is_ascii_uppercase()) {
+            TlTypeRef::Boxed { name }
+        } else {
+            TlTypeRef::Bare { name }
+        }
+    }
+
+    /// the written type as a single identifier, boxed names are reused instead of formatted again
+    pub fn ident(&self) -> TlIdent {
+        match self {
+            TlTypeRef::Boxed { name } => name.clone(),
+            t => TlIdent::new(&t.to_string()),
+        }
+    }
+
+    /// the type without the `flags.N?` condition
+    pub fn unflagged(&self) -> &TlTypeRef {
+        match self {
+            TlTypeRef::Flag { inner, .. } => inner.unflagged(),
+            t => t,
+        }
+    }
+
+    /// the innermost type, `flags.0?Vector<Vector<long>>` -> `long`
+    pub fn innermost(&self) -> &TlTypeRef {
+        match self {
+            TlTypeRef::Flag { inner, .. } | TlTypeRef::Vector { inner, .. } => inner.innermost(),
+            t => t,
+        }
+    }
+
+    /// name of the innermost type, `None` for `#`
+    pub fn base_name(&self) -> Option<&str> {
+        match self.innermost() {
+            TlTypeRef::Bare { name } | TlTypeRef::Boxed { name } | TlTypeRef::Generic { name } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// whether the type is wrapped in a vector
+    pub fn is_vector(&self) -> bool {
+        matches!(self.unflagged(), TlTypeRef::Vector { .. })
+    }
+
+    /// `(field, bit)` of a `flags.N?T` type
+    pub fn flag(&self) -> Option<(&str, u8)> {
+        match self {
+            TlTypeRef::Flag { field, bit, .. } => Some((field, *bit)),
+            _ => None,
+        }
+    }
+
+    /// `flags.N?true`, a flag that carries no data
+    pub fn is_true_flag(&self) -> bool {
+        matches!(self, TlTypeRef::Flag { inner, .. } if matches!(&**inner, TlTypeRef::Bare { name } if name == "true"))
+    }
+}
+
+impl Display for TlTypeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TlTypeRef::Bare { name } => {
+                let last = name.rsplit('.').next().unwrap_or(name);
+                if last.starts_with(|c: char| c.is_ascii_uppercase()) {
+                    write!(f, "%{name}")
+                } else {
+                    write!(f, "{name}")
+                }
+            }
+            TlTypeRef::Boxed { name } => write!(f, "{name}"),
+            TlTypeRef::Vector { bare, inner } => write!(f, "{}<{inner}>", if *bare { "vector" } else { "Vector" }),
+            TlTypeRef::Generic { name } => write!(f, "!{name}"),
+            TlTypeRef::Flag { field, bit, inner } => write!(f, "{field}.{bit}?{inner}"),
+            TlTypeRef::Nat => write!(f, "#"),
+        }
+    }
+}
+
+impl FromStr for TlTypeRef {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parser::parse_type(s).map_err(|e| format!("invalid type `{s}`: {}", e.message))
+    }
+}